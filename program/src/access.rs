@@ -0,0 +1,157 @@
+use crate::error::CustomError;
+use crate::role::RoleGrant;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program::invoke_signed;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::create_account;
+use solana_program::sysvar::rent::Rent;
+use solana_program::sysvar::Sysvar;
+use std::ops::{Deref, DerefMut};
+
+pub const ACCESS_SEED: &[u8] = b"access";
+
+/// Ownership and roles of all lootboxes of the authority. They share the vault, so whoever controls one of them
+/// can move the funds of all of them; that's why they are owned, transferred and delegated together.
+/// The account is created by the first transfer or grant, until then the authority owns its lootboxes.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Access {
+    pub version: AccessVersion,
+    pub authority: Pubkey, // seed of the states and the vault
+    pub bump: u8,
+    pub owner: Pubkey,
+    pub pending_owner: Option<Pubkey>, // proposed by the owner, becomes the owner once accepts
+    pub roles: Vec<RoleGrant>,
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum AccessVersion {
+    Version1 = 1,
+}
+
+impl Access {
+    pub const MAX_ROLES: usize = 10;
+    pub const SIZE: usize = 1 + 32 + 1 + 32 + 33 + 4 + Self::MAX_ROLES * (32 + 1);
+
+    pub fn verify_and_load(program_id: &Pubkey, authority: &Pubkey, access_pda: &AccountInfo) -> Result<Access, ProgramError> {
+        let (access_pub, bump) = Pubkey::find_program_address(&[authority.as_ref(), ACCESS_SEED], program_id);
+        if access_pub != *access_pda.key {
+            msg!("Access account {} & generated PDA {} mismatch.", access_pda.key, access_pub);
+            return Err(CustomError::WrongAccessAccount.into());
+        }
+
+        if access_pda.data_is_empty() {
+            return Ok(Access {
+                version: AccessVersion::Version1,
+                authority: *authority,
+                bump,
+                owner: *authority,
+                pending_owner: None,
+                roles: vec![],
+            });
+        }
+
+        if access_pda.owner != program_id {
+            msg!("Access account must be owned by the program.");
+            return Err(CustomError::WrongAccessAccount.into());
+        }
+
+        Self::load_from(access_pda)
+    }
+
+    /// The owner must sign instructions which only the owner can call.
+    pub fn check_owner(&self, admin: &AccountInfo) -> ProgramResult {
+        if !admin.is_signer {
+            return Err(CustomError::WrongSigner.into());
+        }
+
+        if self.owner != *admin.key {
+            msg!("Admin doesn't own the lootboxes of {}.", self.authority);
+            return Err(CustomError::WrongAdminAccount.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn has_role(&self, account: &Pubkey, role: u8) -> bool {
+        self.owner == *account || self.roles.iter().any(|grant| grant.account == *account && grant.has(role))
+    }
+
+    pub fn check_role(&self, account: &Pubkey, role: u8) -> ProgramResult {
+        if !self.has_role(account, role) {
+            msg!("{} doesn't have role {}.", account, role);
+            return Err(CustomError::MissingRole.into());
+        }
+
+        Ok(())
+    }
+
+    /// `payer` pays for the account if it doesn't exist yet.
+    pub fn create_if_empty<'a>(&self,
+                       program_id: &Pubkey,
+                       access_pda: &AccountInfo<'a>,
+                       payer: &AccountInfo<'a>,
+                       system_program: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        if access_pda.data_is_empty() {
+            msg!("Create access account {}.", access_pda.key);
+            let lamports = Rent::get()?.minimum_balance(Self::SIZE);
+
+            invoke_signed(
+                &create_account(
+                    payer.key,
+                    access_pda.key,
+                    lamports,
+                    Self::SIZE as u64,
+                    program_id,
+                ),
+                &[payer.clone(), access_pda.clone(), system_program.clone()],
+                &[&[self.authority.as_ref(), ACCESS_SEED, &[self.bump]]],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save_to(&self, access_pda: &AccountInfo) -> ProgramResult {
+        self.serialize(access_pda.data.borrow_mut().deref_mut())?;
+
+        Ok(())
+    }
+
+    pub fn load_from(access_pda: &AccountInfo) -> Result<Self, ProgramError> {
+        let data = access_pda.data.borrow();
+        let mut buf: &[u8] = data.deref();
+        let access = Access::deserialize(&mut buf)?;
+        Ok(access)
+    }
+}
+
+#[test]
+fn test_roles() {
+    let authority = Pubkey::new_unique();
+    let operator = Pubkey::new_unique();
+    let mut access = Access {
+        version: AccessVersion::Version1,
+        authority,
+        bump: 255,
+        owner: Pubkey::new_unique(),
+        pending_owner: Some(Pubkey::new_unique()),
+        roles: vec![RoleGrant { account: operator, roles: RoleGrant::OPERATOR }],
+    };
+
+    assert!(access.has_role(&access.owner, RoleGrant::TREASURER));
+    assert!(access.has_role(&operator, RoleGrant::OPERATOR));
+    assert!(!access.has_role(&operator, RoleGrant::TREASURER));
+    assert!(!access.has_role(&authority, RoleGrant::OPERATOR)); // the authority loses everything with the transfer
+
+    while access.roles.len() < Access::MAX_ROLES {
+        access.roles.push(RoleGrant { account: Pubkey::new_unique(), roles: RoleGrant::OPERATOR | RoleGrant::TREASURER });
+    }
+    assert_eq!(borsh::to_vec(&access).unwrap().len(), Access::SIZE);
+}
@@ -0,0 +1,45 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::hash::{hashv, Hash};
+use solana_program::pubkey::Pubkey;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Proof that (wallet, allocation) is a leaf of the state allowlist merkle tree.
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct AllowlistProof {
+    pub allocation: u32, // lifetime tickets of the wallet, 0 is unlimited
+    pub proof: Vec<[u8; 32]>, // siblings from the leaf up to the root
+}
+
+/// sha256(0x00, wallet, allocation be)
+pub fn leaf(wallet: &Pubkey, allocation: u32) -> Hash {
+    hashv(&[LEAF_PREFIX, wallet.as_ref(), &allocation.to_be_bytes()])
+}
+
+/// Nodes are sha256(0x01, min(a, b), max(a, b)), so the proof doesn't need positions.
+pub fn verify(root: &[u8; 32], leaf: Hash, proof: &[[u8; 32]]) -> bool {
+    let computed = proof.iter().fold(leaf.to_bytes(), |node, sibling| {
+        let (left, right) = if node <= *sibling { (&node, sibling) } else { (sibling, &node) };
+        hashv(&[NODE_PREFIX, left, right]).to_bytes()
+    });
+
+    computed == *root
+}
+
+#[test]
+fn test_verify() {
+    let wallets: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let leaves: Vec<[u8; 32]> = wallets.iter().map(|wallet| leaf(wallet, 5).to_bytes()).collect();
+    let node = |a: &[u8; 32], b: &[u8; 32]| {
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+        hashv(&[NODE_PREFIX, left, right]).to_bytes()
+    };
+    let pair = node(&leaves[0], &leaves[1]);
+    let root = node(&pair, &leaves[2]);
+
+    assert!(verify(&root, leaf(&wallets[0], 5), &[leaves[1], leaves[2]]));
+    assert!(verify(&root, leaf(&wallets[2], 5), &[pair]));
+    assert!(!verify(&root, leaf(&wallets[0], 6), &[leaves[1], leaves[2]]));
+    assert!(!verify(&root, leaf(&Pubkey::new_unique(), 5), &[pair]));
+}
@@ -0,0 +1,198 @@
+use crate::error::CustomError;
+use crate::token::recipient_for;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::bpf_loader_upgradeable;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+
+pub const CONFIG_SEED: &[u8] = b"config";
+
+// UpgradeableLoaderState::ProgramData is u32 tag, u64 slot, then Option<Pubkey> authority
+const PROGRAM_DATA_TAG: u32 = 3;
+const PROGRAM_DATA_AUTHORITY_OFFSET: usize = 4 + 8;
+
+/// Program-wide settings, a single PDA created by the upgrade authority.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct Config {
+    pub version: ConfigVersion,
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub paused: bool, // stops every lootbox
+    pub fee_bps: u16, // protocol fee from every payment
+    pub treasury: Pubkey, // receives the protocol fee
+    pub allowed_mints: Vec<Pubkey>, // payment mints, empty allows any
+    pub max_tickets_per_buy: u8, // 0 is unlimited
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum ConfigVersion {
+    Version1 = 1,
+}
+
+impl Config {
+    pub const MAX_SIZE: usize = size_of::<Config>() + 1024;
+    pub const BPS: u16 = 10_000;
+    pub const MAX_FEE_BPS: u16 = 1_000; // 10%
+    pub const MAX_ALLOWED_MINTS: usize = 20;
+
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+    }
+
+    /// Until the config is initialized the defaults are used, so lootboxes keep working.
+    pub fn load(program_id: &Pubkey, config_pda: &AccountInfo) -> Result<Config, ProgramError> {
+        let (config_pub, bump) = Self::find_address(program_id);
+        if config_pub != *config_pda.key {
+            msg!("Config account {} & generated PDA {} mismatch.", config_pda.key, config_pub);
+            return Err(CustomError::WrongConfig.into());
+        }
+
+        if !Self::if_initialized(config_pda) {
+            return Ok(Config {
+                version: ConfigVersion::Version1,
+                bump,
+                authority: Pubkey::default(),
+                paused: false,
+                fee_bps: 0,
+                treasury: Pubkey::default(),
+                allowed_mints: vec![],
+                max_tickets_per_buy: 0,
+            });
+        }
+
+        if config_pda.owner != program_id {
+            msg!("Config must be owned by the program.");
+            return Err(CustomError::WrongConfig.into());
+        }
+
+        Self::load_from(config_pda)
+    }
+
+    /// Loads the config for instructions which only the config authority can call.
+    pub fn verify_and_load_owned(program_id: &Pubkey, authority: &AccountInfo, config_pda: &AccountInfo) -> Result<Config, ProgramError> {
+        if !authority.is_signer {
+            return Err(CustomError::WrongSigner.into());
+        }
+
+        if !Self::if_initialized(config_pda) {
+            msg!("Config isn't initialized.");
+            return Err(CustomError::WrongConfig.into());
+        }
+
+        let config = Self::load(program_id, config_pda)?;
+
+        if config.authority != *authority.key {
+            msg!("Config authority is {}.", config.authority);
+            return Err(CustomError::WrongAdminAccount.into());
+        }
+
+        Ok(config)
+    }
+
+    /// Checks that the account is the upgrade authority of the program.
+    pub fn check_upgrade_authority(program_id: &Pubkey, authority: &AccountInfo, program_data: &AccountInfo) -> ProgramResult {
+        let (program_data_pub, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+        if program_data_pub != *program_data.key || *program_data.owner != bpf_loader_upgradeable::id() {
+            msg!("Program data account must be {}.", program_data_pub);
+            return Err(CustomError::WrongProgramData.into());
+        }
+
+        let data = program_data.data.borrow();
+        let tag = data.get(0..4).map(|tag| u32::from_le_bytes([tag[0], tag[1], tag[2], tag[3]]));
+        let upgrade_authority = data.get(PROGRAM_DATA_AUTHORITY_OFFSET..PROGRAM_DATA_AUTHORITY_OFFSET + 33)
+            .filter(|option| option[0] == 1)
+            .map(|option| &option[1..]);
+
+        if tag != Some(PROGRAM_DATA_TAG) || upgrade_authority != Some(authority.key.as_ref()) {
+            msg!("{} isn't the upgrade authority.", authority.key);
+            return Err(CustomError::WrongAdminAccount.into());
+        }
+
+        Ok(())
+    }
+
+    /// Part of the payment which goes to the treasury, rounded down.
+    pub fn protocol_fee(&self, total: u64) -> u64 {
+        (total as u128 * self.fee_bps as u128 / Self::BPS as u128) as u64
+    }
+
+    pub fn check_treasury(&self, treasury: &AccountInfo, mint: Option<(&AccountInfo, &AccountInfo)>) -> ProgramResult {
+        let expected = recipient_for(&self.treasury, mint);
+
+        if *treasury.key != expected {
+            msg!("Treasury account must be {}.", expected);
+            return Err(CustomError::WrongTreasury.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn check_mint(&self, mint: &Pubkey) -> ProgramResult {
+        if !self.allowed_mints.is_empty() && !self.allowed_mints.contains(mint) {
+            msg!("Payment mint {} isn't allowed.", mint);
+            return Err(CustomError::MintNotAllowed.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn check_tickets_per_buy(&self, count: usize) -> ProgramResult {
+        if self.max_tickets_per_buy != 0 && count > self.max_tickets_per_buy as usize {
+            msg!("At most {} tickets can be bought at once.", self.max_tickets_per_buy);
+            return Err(CustomError::TooManyTickets.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn if_initialized(config_pda: &AccountInfo) -> bool {
+        if config_pda.data_is_empty() {
+            return false;
+        }
+        config_pda.data.borrow()[0] != 0
+    }
+
+    pub fn save_to(&self, config_pda: &AccountInfo) -> ProgramResult {
+        self.serialize(config_pda.data.borrow_mut().deref_mut())?;
+
+        Ok(())
+    }
+
+    pub fn load_from(config_pda: &AccountInfo) -> Result<Self, ProgramError> {
+        let data = config_pda.data.borrow();
+        let mut buf: &[u8] = data.deref();
+        let config = Config::deserialize(&mut buf)?;
+        Ok(config)
+    }
+}
+
+#[test]
+fn test_check_upgrade_authority() {
+    let program_id = Pubkey::new_unique();
+    let (program_data_pub, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    let loader = bpf_loader_upgradeable::id();
+    let upgrade_authority = Pubkey::new_unique();
+
+    let mut data = PROGRAM_DATA_TAG.to_le_bytes().to_vec();
+    data.extend_from_slice(&42u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(upgrade_authority.as_ref());
+    let mut lamports = 0;
+    let program_data = AccountInfo::new(&program_data_pub, false, false, &mut lamports, &mut data, &loader, false, 0);
+
+    let other = Pubkey::new_unique();
+    let mut authority_lamports = 0;
+    let mut other_lamports = 0;
+    let authority = AccountInfo::new(&upgrade_authority, true, false, &mut authority_lamports, &mut [], &other, false, 0);
+    let stranger = AccountInfo::new(&other, true, false, &mut other_lamports, &mut [], &other, false, 0);
+
+    assert_eq!(Config::check_upgrade_authority(&program_id, &authority, &program_data), Ok(()));
+    assert_eq!(Config::check_upgrade_authority(&program_id, &stranger, &program_data), Err(CustomError::WrongAdminAccount.into()));
+}
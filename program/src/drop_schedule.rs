@@ -0,0 +1,86 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::clock::UnixTimestamp;
+use std::convert::TryFrom;
+
+/// Recurring sale windows, e.g. a daily drop: `duration` seconds every `period` seconds starting from `start_ts`,
+/// at most `supply` tickets per window. The state max_supply still caps the lifetime supply.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct DropSchedule {
+    pub start_ts: u32, // begin of the first window
+    pub period: u32,
+    pub duration: u32, // not longer than the period
+    pub supply: u32, // tickets per window
+    pub window_start: u32, // begin of the window counted in issued
+    pub issued: u32,
+}
+
+impl DropSchedule {
+    pub fn new(start_ts: u32, period: u32, duration: u32, supply: u32) -> Self {
+        Self { start_ts, period, duration, supply, window_start: 0, issued: 0 }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.period != 0 && self.duration != 0 && self.duration <= self.period && self.supply != 0
+    }
+
+    /// Begin of the window which is open now.
+    pub fn current_window(&self, now: UnixTimestamp) -> Option<u32> {
+        if now < self.start_ts as UnixTimestamp || self.period == 0 {
+            return None;
+        }
+
+        let elapsed = (now - self.start_ts as UnixTimestamp) as u64;
+        let window_start = self.start_ts as u64 + elapsed / self.period as u64 * self.period as u64;
+        if now as u64 >= window_start + self.duration as u64 {
+            return None;
+        }
+
+        u32::try_from(window_start).ok()
+    }
+
+    /// Tickets left in the window which is open now, 0 if there is none.
+    pub fn tickets_left(&self, now: UnixTimestamp) -> u32 {
+        match self.current_window(now) {
+            Some(window_start) if window_start == self.window_start => self.supply.saturating_sub(self.issued),
+            Some(_) => self.supply, // the first purchase in a new window
+            None => 0,
+        }
+    }
+
+    /// The count is reset when a new window starts.
+    pub fn add_issued(&mut self, count: u32, now: UnixTimestamp) {
+        let Some(window_start) = self.current_window(now) else {
+            return;
+        };
+
+        if window_start != self.window_start {
+            self.window_start = window_start;
+            self.issued = 0;
+        }
+        self.issued += count;
+    }
+}
+
+#[test]
+fn test_drop_windows() {
+    // daily at 18:00 UTC for an hour
+    let start_ts = 1_700_000_000 - 1_700_000_000 % 86_400 + 18 * 3600;
+    let mut schedule = DropSchedule::new(start_ts, 86_400, 3600, 1000);
+    let start = start_ts as UnixTimestamp;
+
+    assert_eq!(schedule.current_window(start - 1), None);
+    assert_eq!(schedule.current_window(start), Some(start_ts));
+    assert_eq!(schedule.current_window(start + 3599), Some(start_ts));
+    assert_eq!(schedule.current_window(start + 3600), None);
+    assert_eq!(schedule.current_window(start + 86_400 + 10), Some(start_ts + 86_400));
+
+    schedule.add_issued(990, start + 10);
+    assert_eq!(schedule.tickets_left(start + 20), 10);
+    assert_eq!(schedule.tickets_left(start + 3600), 0);
+
+    // the next day starts from the full supply
+    assert_eq!(schedule.tickets_left(start + 86_400), 1000);
+    schedule.add_issued(1, start + 86_400);
+    assert_eq!(schedule.issued, 1);
+    assert_eq!(schedule.window_start, start_ts + 86_400);
+}
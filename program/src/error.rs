@@ -50,6 +50,108 @@ pub enum CustomError {
     WrongTicketOwner,
     #[error("The specified instruction is not supported anymore.")]
     InstructionNotSupported,
+    #[error("The specified reward table doesn't belong to the state.")]
+    WrongRewardTable,
+    #[error("There are no rewards left in stock.")]
+    RewardsExhausted,
+    #[error("Accounts for the chosen reward mint weren't specified.")]
+    RewardAccountsNotFound,
+    #[error("The specified ticket was issued by another lootbox.")]
+    WrongTicketState,
+    #[error("The specified randomness account doesn't match with the lootbox configuration.")]
+    WrongRandomnessAccount,
+    #[error("Randomness for the ticket isn't available yet.")]
+    RandomnessNotReady,
+    #[error("Randomness for the ticket isn't available anymore.")]
+    RandomnessExpired,
+    #[error("The specified ticket has been already committed.")]
+    TicketAlreadyCommitted,
+    #[error("The specified ticket must be committed before opening.")]
+    TicketNotCommitted,
+    #[error("The server seed has been already committed.")]
+    SeedAlreadyCommitted,
+    #[error("The server seed wasn't committed.")]
+    SeedNotCommitted,
+    #[error("The server seed has been already revealed.")]
+    SeedAlreadyRevealed,
+    #[error("The server seed doesn't match with the commitment.")]
+    WrongSeed,
+    #[error("The ticket rolls don't match with the server seed.")]
+    WrongRolls,
+    #[error("The specified user account doesn't match with their PDA.")]
+    WrongUserAccount,
+    #[error("The signature has been already used.")]
+    SignatureAlreadyUsed,
+    #[error("The specified nonce doesn't match with the user account.")]
+    WrongNonce,
+    #[error("Signature with high S value is not allowed.")]
+    MalleableSignature,
+    #[error("Ed25519 program instruction with the expected message and key was not found.")]
+    Ed25519InstructionNotFound,
+    #[error("Threshold must be between 1 and the number of signers.")]
+    WrongThreshold,
+    #[error("The signer has been already added.")]
+    SignerAlreadyExists,
+    #[error("The signer wasn't found.")]
+    SignerNotFound,
+    #[error("Too many signers.")]
+    TooManySigners,
+    #[error("Not enough valid signatures.")]
+    NotEnoughSignatures,
+    #[error("The account isn't the pending owner of the lootboxes.")]
+    WrongPendingOwner,
+    #[error("The account doesn't have the required role.")]
+    MissingRole,
+    #[error("Too many accounts with roles.")]
+    TooManyRoles,
+    #[error("The action is paused.")]
+    Paused,
+    #[error("The specified config account doesn't match with the program config PDA.")]
+    WrongConfig,
+    #[error("The specified program data account doesn't belong to the program.")]
+    WrongProgramData,
+    #[error("The fee is out of range.")]
+    WrongFee,
+    #[error("The payment mint isn't allowed.")]
+    MintNotAllowed,
+    #[error("Too many tickets in one purchase.")]
+    TooManyTickets,
+    #[error("The specified treasury account doesn't match with the config.")]
+    WrongTreasury,
+    #[error("Splits must have at most 5 recipients and 10000 bps in total.")]
+    WrongSplits,
+    #[error("The specified split account doesn't match with the price.")]
+    WrongSplitAccount,
+    #[error("The specified referrer account doesn't match with the referrer.")]
+    WrongReferrerAccount,
+    #[error("Buyer can't be their own referrer.")]
+    SelfReferral,
+    #[error("The referrer isn't registered.")]
+    UnregisteredReferrer,
+    #[error("The price has changed since the purchase was signed.")]
+    PriceChanged,
+    #[error("The wallet has reached its tickets limit.")]
+    WalletLimitReached,
+    #[error("The wallet isn't in the allowlist.")]
+    NotAllowlisted,
+    #[error("Phases must be ordered within the sale, not overlap and keep the started ones.")]
+    WrongPhases,
+    #[error("Drop window must be not longer than its period and have a supply.")]
+    WrongDropSchedule,
+    #[error("SKU doesn't exist or isn't specified.")]
+    WrongSku,
+    #[error("SKUs must have unique non zero tiers.")]
+    WrongSkus,
+    #[error("The ticket commit hasn't expired yet.")]
+    CommitNotExpired,
+    #[error("Window limit is greater than the number of tickets a wallet account can track.")]
+    WrongWalletLimits,
+    #[error("Too many prices.")]
+    TooManyPrices,
+    #[error("The randomness can't be changed while tickets are committed.")]
+    TicketsCommitted,
+    #[error("The specified access account doesn't match with the authority PDA.")]
+    WrongAccessAccount,
 }
 
 impl From<CustomError> for ProgramError {
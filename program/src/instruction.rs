@@ -1,6 +1,14 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
+use crate::allowlist::AllowlistProof;
+use crate::message::Action;
+use crate::phase::Phase;
+use crate::randomness::RandomnessConfig;
+use crate::rewards::RewardEntry;
+use crate::signer::Signer;
+use crate::sku::Sku;
+use crate::state::{PriceKind, Split};
 
 #[repr(u8)]
 #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
@@ -11,6 +19,26 @@ pub enum Instruction {
     ObtainTicket(ObtainTicketParams) = 3,
     Buy(BuyParam) = 4,
     Withdraw(WithdrawParam) = 5,
+    Open(OpenParam) = 6,
+    CommitOpen(CommitOpenParam) = 7,
+    RegisterReferrer(RegisterReferrerParams) = 8,
+    ExpireTicket(ExpireTicketParam) = 9,
+    SetSkus(SetSkusParams) = 236,
+    SetPhases(SetPhasesParams) = 237,
+    SetPriceSplits(SetPriceSplitsParams) = 238,
+    UpdateConfig(UpdateConfigParams) = 239,
+    InitializeConfig = 240,
+    RevokeRole(RoleParams) = 241,
+    GrantRole(RoleParams) = 242,
+    AcceptOwner(AcceptOwnerParams) = 243,
+    ProposeOwner(ProposeOwnerParams) = 244,
+    RotateSigner(RotateSignerParams) = 245,
+    RemoveSigner(SignerParams) = 246,
+    AddSigner(SignerParams) = 247,
+    RevealSeed(RevealSeedParams) = 248,
+    CommitSeed(CommitSeedParams) = 249,
+    SetRewards(SetRewardsParams) = 250,
+    MigrateToV5(MigrateToV5Params) = 251,
     UpdateState(UpdateStateParams) = 252,
     MigrateToV3(MigrateToV3Params) = 253,
     AdminWithdraw {
@@ -24,6 +52,11 @@ pub struct BuyParam {
     pub lootbox_id: u16,
     pub ticket_bumps: Vec<u8>,
     pub ticket_seed: u32,
+    pub referrer: Option<Pubkey>,
+    pub expected_mint: Pubkey, // system program id (all zeros) for native prices
+    pub max_total: u64, // price * count the buyer agreed to pay
+    pub allowlist: Option<AllowlistProof>, // required while the state has an allowlist
+    pub sku: Option<u8>, // SKU tier, required while the state has SKUs
 }
 #[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
 pub struct InitializeParams {
@@ -33,10 +66,17 @@ pub struct InitializeParams {
     pub max_supply: u32,
     pub begin_ts: u32,
     pub end_ts: u32,
-    pub signer: [u8; 33],
+    pub signer: Signer,
     pub name: String,
-    pub prices: Vec<u64>,
+    pub prices: Vec<PriceParam>,
     pub base_url: String,
+    pub randomness: RandomnessConfig,
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct PriceParam {
+    pub amount: u64,
+    pub kind: PriceKind,
 }
 
 #[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
@@ -44,6 +84,13 @@ pub struct MigrateToV3Params {
     pub state_bump: u8,
 }
 
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct MigrateToV5Params {
+    pub state_bump: u8,
+    pub lootbox_id: u16,
+    pub legacy_messages_until: u32, // the backends keep signing the old messages until they are upgraded
+}
+
 #[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
 pub struct UpdateStateParams {
     pub state_bump: u8,
@@ -54,6 +101,20 @@ pub struct UpdateStateParams {
     pub end_ts: u32,
     pub price_ata: Pubkey,
     pub price_amount: u64,
+    pub price_kind: PriceKind, // used only for a new price
+    pub randomness: RandomnessConfig,
+    pub legacy_messages_until: u32,
+    pub paused: u8, // State::PAUSE_* bits
+    pub referral_bps: u16,
+    pub referral_registration: bool,
+    pub max_tickets_per_wallet: u32,
+    pub window_limit: u32,
+    pub window_duration: u32,
+    pub allowlist_root: Option<[u8; 32]>,
+    pub drop_period: u32, // 0 disables drops
+    pub drop_start_ts: u32,
+    pub drop_duration: u32,
+    pub drop_supply: u32,
 }
 
 impl UpdateStateParams {
@@ -61,11 +122,24 @@ impl UpdateStateParams {
     const BEGIN_TS: u32 = 2;
     const END_TS: u32 = 4;
     const PRICE: u32 = 8;
+    const NEW_PRICE: u32 = 16;
+    const RANDOMNESS: u32 = 32;
+    const LEGACY_MESSAGES_UNTIL: u32 = 64;
+    const PAUSED: u32 = 128;
+    const REFERRAL: u32 = 256;
+    const WALLET_LIMITS: u32 = 512;
+    const ALLOWLIST: u32 = 1024;
+    const DROP_SCHEDULE: u32 = 2048;
 
     fn is_field(&self, flag: u32) -> bool {
         (self.enabled_fields & flag) == flag
     }
 
+    /// Only fields which a pauser can touch are enabled.
+    pub fn is_only_pause(&self) -> bool {
+        (self.enabled_fields & !(Self::END_TS | Self::PAUSED)) == 0
+    }
+
     pub fn is_max_supply(&self) -> bool {
         self.is_field(Self::MAX_SUPPLY)
     }
@@ -81,6 +155,38 @@ impl UpdateStateParams {
     pub fn is_price(&self) -> bool {
         self.is_field(Self::PRICE)
     }
+
+    pub fn is_new_price(&self) -> bool {
+        self.is_field(Self::NEW_PRICE)
+    }
+
+    pub fn is_randomness(&self) -> bool {
+        self.is_field(Self::RANDOMNESS)
+    }
+
+    pub fn is_legacy_messages_until(&self) -> bool {
+        self.is_field(Self::LEGACY_MESSAGES_UNTIL)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_field(Self::PAUSED)
+    }
+
+    pub fn is_referral(&self) -> bool {
+        self.is_field(Self::REFERRAL)
+    }
+
+    pub fn is_wallet_limits(&self) -> bool {
+        self.is_field(Self::WALLET_LIMITS)
+    }
+
+    pub fn is_allowlist(&self) -> bool {
+        self.is_field(Self::ALLOWLIST)
+    }
+
+    pub fn is_drop_schedule(&self) -> bool {
+        self.is_field(Self::DROP_SCHEDULE)
+    }
 }
 
 #[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
@@ -89,7 +195,7 @@ pub struct ObtainTicketParams {
     pub bump: u8,
     pub id: u32,
     pub expire_at: u32,
-    pub signature: Signature,
+    pub signatures: Vec<Signature>,
 }
 
 #[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
@@ -107,9 +213,142 @@ pub struct Signature {
 pub struct WithdrawParam {
     pub lootbox_id: u16,
     pub expire_at: u32,
-    pub signature: Signature,
+    pub signatures: Vec<Signature>, // Ed25519 signers are verified through the instructions sysvar instead
     pub tickets: u8,
     pub amounts: Vec<u64>, // 1 for NFT
+    pub nonce: u64, // must be equal to the user account withdraw_nonce
+    pub rolls: Vec<u64>, // `randomness::roll` of every ticket while the server seed is committed
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct OpenParam {
+    pub lootbox_id: u16,
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct CommitOpenParam {
+    pub lootbox_id: u16,
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct ExpireTicketParam {
+    pub lootbox_id: u16,
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetRewardsParams {
+    pub lootbox_id: u16,
+    pub rewards_bump: u8,
+    pub entries: Vec<RewardEntry>,
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct CommitSeedParams {
+    pub lootbox_id: u16,
+    pub seed_hash: [u8; 32],
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct RevealSeedParams {
+    pub lootbox_id: u16,
+    pub seed: [u8; 32],
+}
+
+/// The threshold is applied together with the signers change, so the set never requires more signers than it has.
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct SignerParams {
+    pub lootbox_id: u16,
+    pub action: Action,
+    pub signer: Signer,
+    pub threshold: u8,
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct RotateSignerParams {
+    pub lootbox_id: u16,
+    pub action: Action,
+    pub signer: Signer,
+    pub new_signer: Signer,
+    pub previous_valid_until: u32,
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct ProposeOwnerParams {
+    pub lootbox_id: u16,
+    pub new_owner: Option<Pubkey>, // None cancels the proposal
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct AcceptOwnerParams {
+    pub lootbox_id: u16,
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct RoleParams {
+    pub lootbox_id: u16,
+    pub account: Pubkey,
+    pub roles: u8, // RoleGrant bits to grant or revoke
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct UpdateConfigParams {
+    pub enabled_fields: u32,
+    pub paused: bool,
+    pub fee_bps: u16,
+    pub treasury: Pubkey,
+    pub allowed_mints: Vec<Pubkey>,
+    pub max_tickets_per_buy: u8,
+}
+
+impl UpdateConfigParams {
+    const PAUSED: u32 = 1;
+    const FEE: u32 = 2;
+    const ALLOWED_MINTS: u32 = 4;
+    const MAX_TICKETS_PER_BUY: u32 = 8;
+
+    fn is_field(&self, flag: u32) -> bool {
+        (self.enabled_fields & flag) == flag
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_field(Self::PAUSED)
+    }
+
+    pub fn is_fee(&self) -> bool {
+        self.is_field(Self::FEE)
+    }
+
+    pub fn is_allowed_mints(&self) -> bool {
+        self.is_field(Self::ALLOWED_MINTS)
+    }
+
+    pub fn is_max_tickets_per_buy(&self) -> bool {
+        self.is_field(Self::MAX_TICKETS_PER_BUY)
+    }
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetPriceSplitsParams {
+    pub lootbox_id: u16,
+    pub price_ata: Pubkey,
+    pub splits: Vec<Split>,
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetPhasesParams {
+    pub lootbox_id: u16,
+    pub phases: Vec<Phase>, // issued tickets are kept from the current phase with the same begin_ts and end_ts
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct SetSkusParams {
+    pub lootbox_id: u16,
+    pub skus: Vec<Sku>, // total_supply is ignored, nothing is sold before the sale starts
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct RegisterReferrerParams {
+    pub lootbox_id: u16,
 }
 
 impl Instruction {
@@ -125,8 +364,28 @@ impl Instruction {
             Instruction::OldWithdraw => "OldWithdraw (deprecated)",
             Instruction::Buy(_) => "Buy",
             Instruction::Withdraw(_) => "Withdraw",
+            Instruction::Open(_) => "Open",
+            Instruction::CommitOpen(_) => "CommitOpen",
+            Instruction::ExpireTicket(_) => "ExpireTicket",
+            Instruction::RegisterReferrer(_) => "RegisterReferrer",
+            Instruction::SetRewards(_) => "SetRewards",
+            Instruction::CommitSeed(_) => "CommitSeed",
+            Instruction::RevealSeed(_) => "RevealSeed",
+            Instruction::AddSigner(_) => "AddSigner",
+            Instruction::RemoveSigner(_) => "RemoveSigner",
+            Instruction::RotateSigner(_) => "RotateSigner",
+            Instruction::ProposeOwner(_) => "ProposeOwner",
+            Instruction::AcceptOwner(_) => "AcceptOwner",
+            Instruction::GrantRole(_) => "GrantRole",
+            Instruction::RevokeRole(_) => "RevokeRole",
+            Instruction::InitializeConfig => "InitializeConfig",
+            Instruction::UpdateConfig(_) => "UpdateConfig",
+            Instruction::SetPriceSplits(_) => "SetPriceSplits",
+            Instruction::SetPhases(_) => "SetPhases",
+            Instruction::SetSkus(_) => "SetSkus",
             Instruction::ObtainTicket(_) => "ObtainTicket",
             Instruction::MigrateToV3(_) => "MigrationToV3",
+            Instruction::MigrateToV5(_) => "MigrationToV5",
             Instruction::AdminWithdraw { .. } => "AdminWithdraw",
             Instruction::Initialize(_) => "Initialize",
             Instruction::UpdateState(_) => "UpdateState",
@@ -1,20 +1,46 @@
 use solana_program::{account_info::{AccountInfo, next_account_info}, entrypoint, entrypoint::ProgramResult, msg, pubkey::Pubkey};
 use crate::error::CustomError;
 use crate::instruction::Instruction;
-use crate::processors::admin_withdraw::admin_withdraw;
-use crate::processors::buy::buy;
-use crate::processors::initialize::initialize;
-use crate::processors::migrate::migrate_to_v3;
-use crate::processors::obtain::obtain_ticket;
+use crate::processors::admin_withdraw::{admin_withdraw, AdminWithdrawAccounts};
+use crate::processors::buy::{buy, BuyAccounts};
+use crate::processors::commit_open::{commit_open, expire_ticket};
+use crate::processors::config::{initialize_config, update_config};
+use crate::processors::initialize::{initialize, InitializeAccounts};
+use crate::processors::migrate::{migrate_to_v3, migrate_to_v5};
+use crate::processors::obtain::{obtain_ticket, ObtainAccounts};
+use crate::processors::open::{open, OpenAccounts};
+use crate::processors::ownership::{accept_owner, propose_owner};
+use crate::processors::roles::{grant_role, revoke_role};
+use crate::processors::server_seed::{commit_seed, reveal_seed};
+use crate::processors::register_referrer::{register_referrer, RegisterReferrerAccounts};
+use crate::processors::set_price_splits::set_price_splits;
+use crate::processors::set_phases::set_phases;
+use crate::processors::set_skus::set_skus;
+use crate::processors::set_rewards::set_rewards;
+use crate::processors::signers::{add_signer, remove_signer, rotate_signer};
 use crate::processors::update_state::update_state;
-use crate::processors::withdraw::withdraw;
+use crate::processors::withdraw::{withdraw, WithdrawAccounts};
 
+mod access;
 mod instruction;
 mod error;
 mod processors;
 mod state;
 mod verify;
 mod ticket;
+mod token;
+mod rewards;
+mod randomness;
+mod user;
+mod message;
+mod signer;
+mod role;
+mod config;
+mod referrer;
+mod allowlist;
+mod phase;
+mod drop_schedule;
+mod sku;
 
 entrypoint!(process_instruction);
 
@@ -33,50 +59,104 @@ fn process_instruction<'a>(
             return Err(CustomError::InstructionNotSupported.into());
         }
         Instruction::Buy(params) => {
-            let payer = next_account_info(accounts_iter)?;
-            let payer_ata = next_account_info(accounts_iter)?;
-            let payment_ata = next_account_info(accounts_iter)?;
-            let vault_pda = next_account_info(accounts_iter)?;
-            let state_pda = next_account_info(accounts_iter)?;
-            let system_program = next_account_info(accounts_iter)?;
-            let spl_program = next_account_info(accounts_iter)?;
+            let accounts = BuyAccounts {
+                buyer: next_account_info(accounts_iter)?,
+                buyer_ata: next_account_info(accounts_iter)?,
+                payment_ata: next_account_info(accounts_iter)?,
+                vault_pda: next_account_info(accounts_iter)?,
+                state_pda: next_account_info(accounts_iter)?,
+                system_program: next_account_info(accounts_iter)?,
+                spl_program: next_account_info(accounts_iter)?,
+                payment_mint: next_account_info(accounts_iter)?,
+                config_pda: next_account_info(accounts_iter)?,
+                treasury: next_account_info(accounts_iter)?,
+                user_pda: next_account_info(accounts_iter)?,
+            };
 
-            buy(program_id, payer, payer_ata, payment_ata, state_pda, vault_pda,
-                system_program, spl_program, &params, accounts_iter)?;
+            buy(program_id, &accounts, &params, accounts_iter)?;
         }
         Instruction::Withdraw(params) => {
-            let payer = next_account_info(accounts_iter)?;
-            let vault_pda = next_account_info(accounts_iter)?;
-            let state_pda = next_account_info(accounts_iter)?;
-            let system_program = next_account_info(accounts_iter)?;
-            let spl_program = next_account_info(accounts_iter)?;
+            let accounts = WithdrawAccounts {
+                owner: next_account_info(accounts_iter)?,
+                vault_pda: next_account_info(accounts_iter)?,
+                state_pda: next_account_info(accounts_iter)?,
+                system_program: next_account_info(accounts_iter)?,
+                spl_program: next_account_info(accounts_iter)?,
+                token_2022_program: next_account_info(accounts_iter)?,
+                user_pda: next_account_info(accounts_iter)?,
+                instructions_sysvar: next_account_info(accounts_iter)?,
+                config_pda: next_account_info(accounts_iter)?,
+            };
 
-            withdraw(program_id, payer, &params, state_pda, vault_pda, system_program, spl_program, accounts_iter)?;
+            withdraw(program_id, &accounts, &params, accounts_iter)?;
         }
-        Instruction::Initialize(params) => {
-            let admin = next_account_info(accounts_iter)?;
-            let vault_pda = next_account_info(accounts_iter)?;
+        Instruction::Open(params) => {
+            let accounts = OpenAccounts {
+                owner: next_account_info(accounts_iter)?,
+                state_pda: next_account_info(accounts_iter)?,
+                vault_pda: next_account_info(accounts_iter)?,
+                rewards_pda: next_account_info(accounts_iter)?,
+                ticket_pda: next_account_info(accounts_iter)?,
+                randomness_account: next_account_info(accounts_iter)?,
+                system_program: next_account_info(accounts_iter)?,
+                spl_program: next_account_info(accounts_iter)?,
+                token_2022_program: next_account_info(accounts_iter)?,
+                config_pda: next_account_info(accounts_iter)?,
+            };
+
+            open(program_id, &accounts, &params, accounts_iter)?;
+        }
+        Instruction::CommitOpen(params) => {
+            let owner = next_account_info(accounts_iter)?;
             let state_pda = next_account_info(accounts_iter)?;
-            let system_account = next_account_info(accounts_iter)?;
+            let ticket_pda = next_account_info(accounts_iter)?;
+            let randomness_account = next_account_info(accounts_iter)?;
 
-            initialize(program_id, admin, vault_pda, state_pda, system_account, &params, accounts_iter)?;
+            commit_open(program_id, owner, state_pda, ticket_pda, randomness_account, &params)?;
         }
-        Instruction::ObtainTicket(params) => {
-            let payer = next_account_info(accounts_iter)?;
+        Instruction::ExpireTicket(params) => {
             let state_pda = next_account_info(accounts_iter)?;
-            let vault_pda = next_account_info(accounts_iter)?;
             let ticket_pda = next_account_info(accounts_iter)?;
-            let system_account = next_account_info(accounts_iter)?;
-
-            obtain_ticket(
-                program_id,
-                payer,
-                params,
-                state_pda,
-                vault_pda,
-                ticket_pda,
-                system_account,
-            )?;
+            let owner = next_account_info(accounts_iter)?;
+
+            expire_ticket(program_id, state_pda, ticket_pda, owner, &params)?;
+        }
+        Instruction::RegisterReferrer(params) => {
+            let accounts = RegisterReferrerAccounts {
+                referrer: next_account_info(accounts_iter)?,
+                approver: next_account_info(accounts_iter)?,
+                state_pda: next_account_info(accounts_iter)?,
+                stats_pda: next_account_info(accounts_iter)?,
+                system_program: next_account_info(accounts_iter)?,
+                access_pda: next_account_info(accounts_iter)?,
+            };
+
+            register_referrer(program_id, &accounts, params)?;
+        }
+        Instruction::Initialize(params) => {
+            let accounts = InitializeAccounts {
+                admin: next_account_info(accounts_iter)?,
+                vault_pda: next_account_info(accounts_iter)?,
+                state_pda: next_account_info(accounts_iter)?,
+                system_account: next_account_info(accounts_iter)?,
+                access_pda: next_account_info(accounts_iter)?,
+            };
+
+            initialize(program_id, &accounts, &params, accounts_iter)?;
+        }
+        Instruction::ObtainTicket(params) => {
+            let accounts = ObtainAccounts {
+                buyer: next_account_info(accounts_iter)?,
+                state_pda: next_account_info(accounts_iter)?,
+                vault_pda: next_account_info(accounts_iter)?,
+                ticket_pda: next_account_info(accounts_iter)?,
+                system_program: next_account_info(accounts_iter)?,
+                instructions_sysvar: next_account_info(accounts_iter)?,
+                config_pda: next_account_info(accounts_iter)?,
+                user_pda: next_account_info(accounts_iter)?,
+            };
+
+            obtain_ticket(program_id, &accounts, params)?;
         }
         Instruction::MigrateToV3(params) => {
             let admin = next_account_info(accounts_iter)?;
@@ -84,22 +164,141 @@ fn process_instruction<'a>(
 
             migrate_to_v3(program_id, admin, state_pda, params)?;
         }
+        Instruction::MigrateToV5(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+
+            migrate_to_v5(program_id, admin, state_pda, system_program, params)?;
+        }
         Instruction::UpdateState(params) => {
             let admin = next_account_info(accounts_iter)?;
             let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
 
-            update_state(program_id, admin, state_pda, params)?;
+            update_state(program_id, admin, state_pda, access_pda, params)?;
 
         }
-        Instruction::AdminWithdraw { lootbox_id, amount } => {
+        Instruction::SetRewards(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let rewards_pda = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+
+            set_rewards(program_id, admin, state_pda, access_pda, rewards_pda, system_program, params)?;
+        }
+        Instruction::CommitSeed(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+
+            commit_seed(program_id, admin, state_pda, access_pda, params)?;
+        }
+        Instruction::RevealSeed(params) => {
+            let state_pda = next_account_info(accounts_iter)?;
+
+            reveal_seed(program_id, state_pda, params)?;
+        }
+        Instruction::AddSigner(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+
+            add_signer(program_id, admin, state_pda, access_pda, params)?;
+        }
+        Instruction::RemoveSigner(params) => {
             let admin = next_account_info(accounts_iter)?;
             let state_pda = next_account_info(accounts_iter)?;
-            let vault_pda = next_account_info(accounts_iter)?;
-            let source_ata = next_account_info(accounts_iter)?;
-            let destination_ata = next_account_info(accounts_iter)?;
-            let spl_program = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+
+            remove_signer(program_id, admin, state_pda, access_pda, params)?;
+        }
+        Instruction::RotateSigner(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+
+            rotate_signer(program_id, admin, state_pda, access_pda, params)?;
+        }
+        Instruction::ProposeOwner(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+
+            propose_owner(program_id, admin, state_pda, access_pda, system_program, params)?;
+        }
+        Instruction::AcceptOwner(params) => {
+            let new_owner = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+
+            accept_owner(program_id, new_owner, state_pda, access_pda, params)?;
+        }
+        Instruction::GrantRole(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+
+            grant_role(program_id, admin, state_pda, access_pda, system_program, params)?;
+        }
+        Instruction::RevokeRole(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+
+            revoke_role(program_id, admin, state_pda, access_pda, params)?;
+        }
+        Instruction::InitializeConfig => {
+            let authority = next_account_info(accounts_iter)?;
+            let config_pda = next_account_info(accounts_iter)?;
+            let program_data = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+
+            initialize_config(program_id, authority, config_pda, program_data, system_program)?;
+        }
+        Instruction::UpdateConfig(params) => {
+            let authority = next_account_info(accounts_iter)?;
+            let config_pda = next_account_info(accounts_iter)?;
+
+            update_config(program_id, authority, config_pda, params)?;
+        }
+        Instruction::SetPriceSplits(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+
+            set_price_splits(program_id, admin, state_pda, access_pda, params)?;
+        }
+        Instruction::SetPhases(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+
+            set_phases(program_id, admin, state_pda, access_pda, params)?;
+        }
+        Instruction::SetSkus(params) => {
+            let admin = next_account_info(accounts_iter)?;
+            let state_pda = next_account_info(accounts_iter)?;
+            let access_pda = next_account_info(accounts_iter)?;
+
+            set_skus(program_id, admin, state_pda, access_pda, params)?;
+        }
+        Instruction::AdminWithdraw { lootbox_id, amount } => {
+            let accounts = AdminWithdrawAccounts {
+                admin: next_account_info(accounts_iter)?,
+                state_pda: next_account_info(accounts_iter)?,
+                vault_pda: next_account_info(accounts_iter)?,
+                source_ata: next_account_info(accounts_iter)?,
+                destination_ata: next_account_info(accounts_iter)?,
+                spl_program: next_account_info(accounts_iter)?,
+                mint: next_account_info(accounts_iter)?,
+                access_pda: next_account_info(accounts_iter)?,
+            };
 
-            admin_withdraw(program_id, lootbox_id, admin, state_pda, vault_pda, source_ata, destination_ata, spl_program, amount)?;
+            admin_withdraw(program_id, &accounts, lootbox_id, amount)?;
         }
     }
 
@@ -0,0 +1,292 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::hash::{Hash, Hasher};
+use solana_program::keccak;
+use solana_program::pubkey::Pubkey;
+
+pub const MESSAGE_DOMAIN: &[u8] = b"AgoraDex Lootbox";
+pub const MESSAGE_VERSION: u8 = 3;
+
+const EIP191_PREFIX: &[u8] = b"\x19Ethereum Signed Message:\n32";
+const EIP712_PREFIX: &[u8] = b"\x19\x01";
+const EIP712_DOMAIN_TYPE: &[u8] = b"EIP712Domain(string name,string version,bytes32 salt)";
+const EIP712_OBTAIN_TYPE: &[u8] = b"ObtainTicket(bytes32 program,bytes32 state,uint16 lootboxId,bytes32 vault,bytes32 buyer,uint32 id,uint32 expireAt)";
+const EIP712_WITHDRAW_TYPE: &[u8] = b"Withdraw(bytes32 program,bytes32 state,uint16 lootboxId,bytes32[] tickets,uint8[] tiers,uint64[] rolls,Reward[] rewards,bytes32 vault,bytes32 owner,uint64 nonce,uint32 expireAt)Reward(bytes32 mint,uint64 amount)";
+const EIP712_REWARD_TYPE: &[u8] = b"Reward(bytes32 mint,uint64 amount)";
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+#[borsh(use_discriminant = true)]
+pub enum Action {
+    Obtain = 1,
+    Withdraw = 2,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reward {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MessageBody {
+    Obtain {
+        vault: Pubkey,
+        buyer: Pubkey,
+        id: u32,
+    },
+    Withdraw {
+        tickets: Vec<Pubkey>,
+        tiers: Vec<u8>, // SKU tier of every ticket
+        rolls: Vec<u64>, // roll of every ticket from the server seed, empty if the seed isn't committed
+        rewards: Vec<Reward>,
+        vault: Pubkey,
+        owner: Pubkey,
+        nonce: u64,
+    },
+}
+
+/// Everything the backend signs to authorize an action.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    pub program_id: Pubkey,
+    pub state: Pubkey,
+    pub lootbox_id: u16,
+    pub expire_at: u32,
+    pub body: MessageBody,
+}
+
+impl Message {
+    pub fn action(&self) -> Action {
+        match self.body {
+            MessageBody::Obtain { .. } => Action::Obtain,
+            MessageBody::Withdraw { .. } => Action::Withdraw,
+        }
+    }
+
+    /// sha256(domain, version, program id, state, lootbox id, action, fields..., expire_at), withdraw fields include ticket tiers and rolls
+    pub fn hash(&self) -> Hash {
+        let mut hasher = Hasher::default();
+        hasher.hash(MESSAGE_DOMAIN);
+        hasher.hash(&[MESSAGE_VERSION]);
+        hasher.hash(&self.program_id.to_bytes());
+        hasher.hash(&self.state.to_bytes());
+        hasher.hash(&self.lootbox_id.to_be_bytes());
+        hasher.hash(&[self.action() as u8]);
+        self.hash_fields(&mut hasher);
+        hasher.result()
+    }
+
+    /// The format signed before domain separation, it's accepted only during the transition period:
+    /// sha256(vault, buyer, id, expire_at) for obtain and sha256(tickets..., (mint, amount)..., vault, owner, expire_at) for withdraw.
+    /// It doesn't cover the nonce and the rolls, so withdraws without tickets (replayable) or with rolls aren't accepted in it.
+    pub fn legacy_hash(&self) -> Option<Hash> {
+        let mut hasher = Hasher::default();
+        match &self.body {
+            MessageBody::Obtain { vault, buyer, id } => {
+                hasher.hash(&vault.to_bytes());
+                hasher.hash(&buyer.to_bytes());
+                hasher.hash(&id.to_be_bytes());
+            }
+            MessageBody::Withdraw { tickets, rolls, rewards, vault, owner, .. } => {
+                if tickets.is_empty() || !rolls.is_empty() {
+                    return None;
+                }
+                for ticket in tickets {
+                    hasher.hash(&ticket.to_bytes());
+                }
+                for reward in rewards {
+                    hasher.hash(&reward.mint.to_bytes());
+                    hasher.hash(&reward.amount.to_be_bytes());
+                }
+                hasher.hash(&vault.to_bytes());
+                hasher.hash(&owner.to_bytes());
+            }
+        }
+        hasher.hash(&self.expire_at.to_be_bytes());
+        Some(hasher.result())
+    }
+
+    /// keccak256("\x19Ethereum Signed Message:\n32", hash()), i.e. personal_sign of the 32 bytes hash
+    pub fn eip191_hash(&self) -> keccak::Hash {
+        keccak::hashv(&[EIP191_PREFIX, &self.hash().to_bytes()])
+    }
+
+    /// keccak256("\x19\x01", domainSeparator, hashStruct(message)),
+    /// the domain is named after MESSAGE_DOMAIN with the program id as salt.
+    pub fn eip712_hash(&self) -> keccak::Hash {
+        let domain = keccak::hashv(&[
+            &keccak::hash(EIP712_DOMAIN_TYPE).to_bytes(),
+            &keccak::hash(MESSAGE_DOMAIN).to_bytes(),
+            &keccak::hash(MESSAGE_VERSION.to_string().as_bytes()).to_bytes(),
+            &self.program_id.to_bytes(),
+        ]);
+
+        keccak::hashv(&[EIP712_PREFIX, &domain.to_bytes(), &self.eip712_struct_hash().to_bytes()])
+    }
+
+    fn eip712_struct_hash(&self) -> keccak::Hash {
+        let mut hasher = keccak::Hasher::default();
+        match &self.body {
+            MessageBody::Obtain { .. } => hasher.hash(&keccak::hash(EIP712_OBTAIN_TYPE).to_bytes()),
+            MessageBody::Withdraw { .. } => hasher.hash(&keccak::hash(EIP712_WITHDRAW_TYPE).to_bytes()),
+        }
+        hasher.hash(&self.program_id.to_bytes());
+        hasher.hash(&self.state.to_bytes());
+        hasher.hash(&eip712_uint(self.lootbox_id as u64));
+
+        match &self.body {
+            MessageBody::Obtain { vault, buyer, id } => {
+                hasher.hash(&vault.to_bytes());
+                hasher.hash(&buyer.to_bytes());
+                hasher.hash(&eip712_uint(*id as u64));
+            }
+            MessageBody::Withdraw { tickets, tiers, rolls, rewards, vault, owner, nonce } => {
+                let tickets: Vec<[u8; 32]> = tickets.iter().map(|ticket| ticket.to_bytes()).collect();
+                let tiers: Vec<[u8; 32]> = tiers.iter().map(|tier| eip712_uint(*tier as u64)).collect();
+                let rolls: Vec<[u8; 32]> = rolls.iter().map(|roll| eip712_uint(*roll)).collect();
+                let rewards: Vec<[u8; 32]> = rewards.iter()
+                    .map(|reward| keccak::hashv(&[
+                        &keccak::hash(EIP712_REWARD_TYPE).to_bytes(),
+                        &reward.mint.to_bytes(),
+                        &eip712_uint(reward.amount),
+                    ]).to_bytes())
+                    .collect();
+
+                // arrays are encoded as the hash of the concatenated encoded items
+                hasher.hash(&keccak::hashv(&tickets.iter().map(|item| item.as_ref()).collect::<Vec<_>>()).to_bytes());
+                hasher.hash(&keccak::hashv(&tiers.iter().map(|item| item.as_ref()).collect::<Vec<_>>()).to_bytes());
+                hasher.hash(&keccak::hashv(&rolls.iter().map(|item| item.as_ref()).collect::<Vec<_>>()).to_bytes());
+                hasher.hash(&keccak::hashv(&rewards.iter().map(|item| item.as_ref()).collect::<Vec<_>>()).to_bytes());
+                hasher.hash(&vault.to_bytes());
+                hasher.hash(&owner.to_bytes());
+                hasher.hash(&eip712_uint(*nonce));
+            }
+        }
+        hasher.hash(&eip712_uint(self.expire_at as u64));
+
+        hasher.result()
+    }
+
+    fn hash_fields(&self, hasher: &mut Hasher) {
+        match &self.body {
+            MessageBody::Obtain { vault, buyer, id } => {
+                hasher.hash(&vault.to_bytes());
+                hasher.hash(&buyer.to_bytes());
+                hasher.hash(&id.to_be_bytes());
+            }
+            MessageBody::Withdraw { tickets, tiers, rolls, rewards, vault, owner, nonce } => {
+                for ticket in tickets {
+                    hasher.hash(&ticket.to_bytes());
+                }
+                hasher.hash(tiers);
+                for roll in rolls {
+                    hasher.hash(&roll.to_be_bytes());
+                }
+                for reward in rewards {
+                    hasher.hash(&reward.mint.to_bytes());
+                    hasher.hash(&reward.amount.to_be_bytes());
+                }
+                hasher.hash(&vault.to_bytes());
+                hasher.hash(&owner.to_bytes());
+                hasher.hash(&nonce.to_be_bytes());
+            }
+        }
+        hasher.hash(&self.expire_at.to_be_bytes());
+    }
+}
+
+/// ABI encoding of an unsigned integer: 32 bytes, big endian
+fn eip712_uint(value: u64) -> [u8; 32] {
+    let mut buf = [0u8; 32];
+    buf[24..].copy_from_slice(&value.to_be_bytes());
+    buf
+}
+
+#[test]
+fn test_legacy_hash() {
+    // the layout signed by the backends before domain separation
+    let obtain = Message {
+        program_id: Pubkey::new_unique(),
+        state: Pubkey::new_unique(),
+        lootbox_id: 1,
+        expire_at: 1000,
+        body: MessageBody::Obtain {
+            vault: Pubkey::new_from_array([1; 32]),
+            buyer: Pubkey::new_from_array([2; 32]),
+            id: 7,
+        },
+    };
+    assert_eq!(obtain.legacy_hash().unwrap().to_bytes(), [
+        0x0f, 0x8c, 0xe1, 0x26, 0x4c, 0x4e, 0x0a, 0xc1, 0xf1, 0xeb, 0x90, 0x23, 0x38, 0xef, 0x8f, 0x45,
+        0x0a, 0x7b, 0x63, 0xa5, 0xbc, 0x51, 0xa6, 0x7a, 0x58, 0x1e, 0xfc, 0xbf, 0x95, 0x6d, 0xd8, 0xc5,
+    ]);
+
+    let mut withdraw = Message {
+        body: MessageBody::Withdraw {
+            tickets: vec![Pubkey::new_from_array([3; 32])],
+            tiers: vec![0],
+            rolls: vec![],
+            rewards: vec![Reward { mint: Pubkey::new_from_array([4; 32]), amount: 5 }],
+            vault: Pubkey::new_from_array([1; 32]),
+            owner: Pubkey::new_from_array([2; 32]),
+            nonce: 9,
+        },
+        ..obtain
+    };
+    assert_eq!(withdraw.legacy_hash().unwrap().to_bytes(), [
+        0xed, 0x77, 0x6b, 0x73, 0x35, 0x79, 0xb5, 0xaa, 0x96, 0x51, 0x62, 0x10, 0x10, 0xc7, 0x21, 0x16,
+        0x2e, 0x18, 0x4b, 0x11, 0x20, 0x8a, 0x6a, 0x1b, 0x09, 0x48, 0x19, 0x0d, 0x4e, 0xc2, 0xea, 0xea,
+    ]);
+
+    // without tickets the legacy signature could be replayed
+    if let MessageBody::Withdraw { tickets, .. } = &mut withdraw.body {
+        tickets.clear();
+    }
+    assert_eq!(withdraw.legacy_hash(), None);
+}
+
+#[test]
+fn test_ethereum_hashes() {
+    // expected values are computed by an independent keccak256/EIP-712 encoder,
+    // checked against the EIP-712 Mail example and ethers hashMessage("Hello World")
+    let obtain = Message {
+        program_id: Pubkey::new_from_array([9; 32]),
+        state: Pubkey::new_from_array([8; 32]),
+        lootbox_id: 1,
+        expire_at: 1000,
+        body: MessageBody::Obtain {
+            vault: Pubkey::new_from_array([1; 32]),
+            buyer: Pubkey::new_from_array([2; 32]),
+            id: 7,
+        },
+    };
+    assert_eq!(obtain.eip191_hash().to_bytes(), [
+        0xc1, 0xda, 0xf7, 0xdf, 0x25, 0x2c, 0x7d, 0x77, 0xb8, 0x88, 0xb1, 0x1f, 0x66, 0xdf, 0xd6, 0xe8,
+        0x4b, 0xd4, 0x16, 0xa5, 0x30, 0x16, 0x60, 0x5a, 0x3e, 0x97, 0x02, 0xb9, 0x95, 0x07, 0x66, 0x96,
+    ]);
+    assert_eq!(obtain.eip712_hash().to_bytes(), [
+        0xa3, 0xfd, 0xe0, 0x77, 0xa3, 0x32, 0xac, 0xd2, 0x94, 0xf9, 0xdf, 0x09, 0x81, 0x9a, 0x1c, 0x57,
+        0x87, 0x52, 0x7d, 0x3e, 0x1f, 0x12, 0x1f, 0xf7, 0xdc, 0xbb, 0x8f, 0xec, 0x91, 0x7e, 0x2c, 0x24,
+    ]);
+
+    let withdraw = Message {
+        body: MessageBody::Withdraw {
+            tickets: vec![Pubkey::new_from_array([3; 32])],
+            tiers: vec![1],
+            rolls: vec![42],
+            rewards: vec![Reward { mint: Pubkey::new_from_array([4; 32]), amount: 5 }],
+            vault: Pubkey::new_from_array([1; 32]),
+            owner: Pubkey::new_from_array([2; 32]),
+            nonce: 9,
+        },
+        ..obtain
+    };
+    assert_eq!(withdraw.eip191_hash().to_bytes(), [
+        0x69, 0x73, 0xad, 0x0f, 0x08, 0x93, 0x6e, 0xf7, 0x03, 0x92, 0x80, 0xaa, 0x22, 0xc4, 0x5c, 0xc5,
+        0x7b, 0x96, 0x42, 0x7a, 0xe9, 0x8a, 0x82, 0xd8, 0x28, 0x03, 0x25, 0x4f, 0x0b, 0xae, 0x54, 0x91,
+    ]);
+    assert_eq!(withdraw.eip712_hash().to_bytes(), [
+        0xf0, 0x7d, 0x55, 0x12, 0x7c, 0x64, 0x12, 0x49, 0xda, 0x2b, 0xaa, 0x1b, 0xc5, 0xcc, 0x53, 0xce,
+        0x3b, 0xb6, 0x53, 0x6e, 0x25, 0xe8, 0x4f, 0xaa, 0x79, 0x0e, 0x45, 0xe9, 0xcd, 0xc7, 0x54, 0x9e,
+    ]);
+}
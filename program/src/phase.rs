@@ -0,0 +1,70 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::clock::UnixTimestamp;
+use solana_program::pubkey::Pubkey;
+use std::mem::size_of;
+
+/// Part of the sale with its own schedule, prices, supply and eligibility.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct Phase {
+    pub begin_ts: u32,
+    pub end_ts: u32,
+    pub price_overrides: Vec<PriceOverride>, // prices which aren't overridden are taken from the state
+    pub max_supply: u32, // tickets issued during the phase, 0 is limited only by the state supply
+    pub issued: u32,
+    pub eligibility: Eligibility,
+}
+
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct PriceOverride {
+    pub ata: Pubkey, // payment account of the state price
+    pub amount: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub enum Eligibility {
+    // the state allowlist applies, if any
+    Public,
+    // only wallets from this merkle tree, see `allowlist`
+    Allowlist { root: [u8; 32] },
+}
+
+impl Phase {
+    pub const MAX_PRICE_OVERRIDES: usize = 4;
+    pub const MAX_SIZE: usize = size_of::<Phase>() + Self::MAX_PRICE_OVERRIDES * size_of::<PriceOverride>();
+
+    pub fn is_active(&self, now: UnixTimestamp) -> bool {
+        (self.begin_ts as UnixTimestamp) <= now && now <= (self.end_ts as UnixTimestamp)
+    }
+
+    pub fn tickets_left(&self) -> Option<u32> {
+        if self.max_supply == 0 {
+            return None;
+        }
+
+        Some(self.max_supply.saturating_sub(self.issued))
+    }
+}
+
+#[test]
+fn test_phase_supply() {
+    let mut phase = Phase {
+        begin_ts: 10,
+        end_ts: 20,
+        price_overrides: vec![],
+        max_supply: 5,
+        issued: 3,
+        eligibility: Eligibility::Public,
+    };
+
+    assert!(!phase.is_active(9));
+    assert!(phase.is_active(10));
+    assert!(phase.is_active(20));
+    assert!(!phase.is_active(21));
+    assert_eq!(phase.tickets_left(), Some(2));
+
+    phase.issued = 6;
+    assert_eq!(phase.tickets_left(), Some(0));
+
+    phase.max_supply = 0;
+    assert_eq!(phase.tickets_left(), None);
+}
@@ -1,56 +1,53 @@
 use solana_program::account_info::AccountInfo;
 use solana_program::entrypoint::ProgramResult;
-use solana_program::msg;
-use solana_program::program::invoke_signed;
 use solana_program::pubkey::Pubkey;
-use spl_token::instruction::transfer;
 
-use crate::error::CustomError;
-use crate::state::{State, VAULT};
+use crate::processors::roles::verify_and_load_with_role;
+use crate::role::RoleGrant;
+use crate::state::VAULT;
+use crate::token::transfer;
+
+#[derive(Clone, Copy)]
+pub struct AdminWithdrawAccounts<'a, 'b> {
+    pub admin: &'b AccountInfo<'a>,
+    pub state_pda: &'b AccountInfo<'a>,
+    pub vault_pda: &'b AccountInfo<'a>,
+    pub source_ata: &'b AccountInfo<'a>,
+    pub destination_ata: &'b AccountInfo<'a>,
+    pub spl_program: &'b AccountInfo<'a>,
+    pub mint: &'b AccountInfo<'a>,
+    pub access_pda: &'b AccountInfo<'a>,
+}
 
 pub fn admin_withdraw<'a>(program_id: &Pubkey,
+                          accounts: &AdminWithdrawAccounts<'a, '_>,
                           lootbox_id: u16,
-                          admin: &AccountInfo<'a>,
-                          state_pda: &AccountInfo<'a>,
-                          vault_pda: &AccountInfo<'a>,
-                          source_ata: &AccountInfo<'a>,
-                          destination_ata: &AccountInfo<'a>,
-                          spl_program: &AccountInfo<'a>,
                           amount: u64,
 ) -> ProgramResult {
-    if !admin.is_signer {
-        msg!("Admin is not signer.");
-        return Err(CustomError::WrongSigner.into());
-    }
-
-    let state = State::verify_and_load(program_id, state_pda, lootbox_id, None)?;
+    let AdminWithdrawAccounts {
+        admin,
+        state_pda,
+        vault_pda,
+        source_ata,
+        destination_ata,
+        spl_program,
+        mint,
+        access_pda,
+    } = *accounts;
 
-    if state.owner != *admin.key {
-        msg!("Admin doesn't own the state.");
-        return Err(CustomError::WrongAdminAccount.into());
-    }
+    let state = verify_and_load_with_role(program_id, admin, state_pda, access_pda, lootbox_id, RoleGrant::TREASURER)?;
 
-    let vault_seed = [&state.owner.to_bytes(), VAULT, &[state.vault_bump]];
+    let vault_seed = [&state.authority.to_bytes(), VAULT, &[state.vault_bump]];
 
     state.check_vault_with_seed(program_id, vault_pda, &vault_seed)?;
 
-    invoke_signed(
-        &transfer(
-            spl_program.key,
-            source_ata.key,
-            destination_ata.key,
-            vault_pda.key,
-            &[],
-            amount,
-        )?,
-        &[
-            source_ata.clone(),
-            destination_ata.clone(),
-            vault_pda.clone(),
-            spl_program.clone(),
-        ],
+    transfer(
+        spl_program,
+        source_ata,
+        mint,
+        destination_ata,
+        vault_pda,
+        amount,
         &[&vault_seed],
-    )?;
-
-    Ok(())
+    )
 }
@@ -2,60 +2,132 @@ use solana_program::account_info::{next_account_info, AccountInfo};
 use solana_program::clock::Clock;
 use solana_program::entrypoint::ProgramResult;
 use solana_program::msg;
-use solana_program::program::invoke_signed;
+use solana_program::program::invoke;
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
+use solana_program::{system_instruction, system_program};
 use solana_program::sysvar::Sysvar;
-use spl_token::instruction::transfer;
 use std::convert::Into;
 use std::slice::Iter;
 
+use crate::allowlist;
+use crate::config::Config;
 use crate::error::CustomError;
 use crate::instruction::BuyParam;
-use crate::state::State;
+use crate::referrer::ReferrerStats;
+use crate::state::{Price, PriceKind, State};
 use crate::ticket::Ticket;
+use crate::token::{amount_with_fee, check_token_program, recipient_for, transfer};
+use crate::user::UserState;
+
+#[derive(Clone, Copy)]
+pub struct BuyAccounts<'a, 'b> {
+    pub buyer: &'b AccountInfo<'a>,
+    pub buyer_ata: &'b AccountInfo<'a>,
+    pub payment_ata: &'b AccountInfo<'a>,
+    pub vault_pda: &'b AccountInfo<'a>,
+    pub state_pda: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub spl_program: &'b AccountInfo<'a>,
+    pub payment_mint: &'b AccountInfo<'a>,
+    pub config_pda: &'b AccountInfo<'a>,
+    pub treasury: &'b AccountInfo<'a>,
+    pub user_pda: &'b AccountInfo<'a>,
+}
 
 pub fn buy<'a>(program_id: &Pubkey,
-               buyer: &AccountInfo<'a>,
-               buyer_ata: &AccountInfo<'a>,
-               payment_ata: &AccountInfo<'a>,
-               state_pda: &AccountInfo<'a>,
-               vault_pda: &AccountInfo<'a>,
-               system_program: &AccountInfo<'a>,
-               spl_program: &AccountInfo<'a>,
+               accounts: &BuyAccounts<'a, '_>,
                params: &BuyParam,
                accounts_iter: &mut Iter<AccountInfo<'a>>,
 ) -> ProgramResult {
+    let BuyAccounts {
+        buyer,
+        buyer_ata,
+        payment_ata,
+        vault_pda,
+        state_pda,
+        system_program,
+        spl_program,
+        payment_mint,
+        config_pda,
+        treasury,
+        user_pda,
+    } = *accounts;
+
     if !buyer.is_signer {
         msg!("Buyer must be signer.");
         return Err(CustomError::WrongSigner.into());
     }
 
     let mut state = State::verify_and_load(program_id,  state_pda, params.lootbox_id, None)?;
+    let config = Config::load(program_id, config_pda)?;
+    state.check_not_paused(&config, State::PAUSE_BUY)?;
+
+    config.check_tickets_per_buy(params.ticket_bumps.len())?;
 
-    let count = state.check_and_get_correct_count(params.ticket_bumps.len() as u8)?;
+    let clock = Clock::get()?;
+    state.check_time(&clock)?;
+    if !state.skus.is_empty() && params.sku.is_none() {
+        msg!("SKU must be specified.");
+        return Err(CustomError::WrongSku.into());
+    }
+    let sku = state.find_sku(params.sku)?;
+    let count = state.check_and_get_correct_count(params.ticket_bumps.len() as u8, sku, &clock)?;
+    let count = state.check_drop_window(count, &clock)?;
     state.check_vault(program_id, vault_pda)?;
-    state.check_time(&Clock::get()?)?;
 
-    accept_payment(
-        &state,
+    let allowlist_root = state.allowlist_root(&clock);
+    let allocation = match &allowlist_root {
+        Some(root) => check_allowlist(root, buyer, params)?,
+        None => None,
+    };
+
+    let referral = match params.referrer {
+        Some(referrer) => Some(load_referral(program_id, state_pda, buyer, referrer, accounts_iter)?),
+        None => None,
+    };
+
+    let price = state.find_price(payment_ata, sku, &clock)?;
+
+    // the allocation is counted in the wallet account too
+    if state.has_wallet_limits() || allocation.is_some() {
+        let mut user = UserState::load_or_create(program_id, state_pda, buyer, user_pda, system_program)?;
+        user.add_tickets(&state, count as u32, allocation, clock.unix_timestamp as u32)?;
+        user.save_to(user_pda)?;
+    }
+
+    let payment = Payment {
+        price,
         buyer,
         buyer_ata,
         payment_ata,
+        payment_mint,
+        treasury,
+        system_program,
         spl_program,
-        count,
-    )?;
+    };
+    let total = accept_payment(&state, &config, &payment, count, params, referral.as_ref(), accounts_iter)?;
+
+    if let Some(mut referral) = referral {
+        referral.stats.purchases += 1;
+        referral.stats.tickets += count as u32;
+        referral.stats.volume = referral.stats.volume.saturating_add(total);
+        msg!("Referrer {} has {} purchases for {}.", referral.stats.referrer, referral.stats.purchases, referral.stats.volume);
+        referral.stats.save_to(referral.stats_pda)?;
+    }
 
     { // walk through tickets
         let mut issue_index = state.total_supply;
         let mut index = 0;
-        for ticket_bump in &params.ticket_bumps {
+        // the count is limited by the supply left, the rest of bumps is ignored
+        for ticket_bump in params.ticket_bumps.iter().take(count as usize) {
             let ticket_pda = next_account_info(accounts_iter)?;
 
             Ticket::verify_and_create(
                 program_id,
                 system_program,
                 buyer,
+                state_pda.key,
                 params.lootbox_id,
                 params.ticket_seed,
                 index,
@@ -63,6 +135,7 @@ pub fn buy<'a>(program_id: &Pubkey,
                 ticket_pda,
                 Some(*ticket_bump),
                 None,
+                params.sku.unwrap_or(0),
             )?;
 
             issue_index += 1;
@@ -70,52 +143,174 @@ pub fn buy<'a>(program_id: &Pubkey,
         }
     }
 
-    state.total_supply += count as u32;
+    state.add_issued(count as u32, params.sku, &clock);
+    state.add_dropped(count as u32, &clock);
     state.save_to(state_pda)?;
 
     Ok(())
 }
 
+/// Verifies the allowlist proof of the buyer and returns their allocation, zero allocation is unlimited.
+/// The allocation is checked against the lifetime tickets of the wallet, so it's counted in the wallet account.
+fn check_allowlist(root: &[u8; 32], buyer: &AccountInfo, params: &BuyParam) -> Result<Option<u32>, ProgramError> {
+    let Some(allowlist) = &params.allowlist else {
+        msg!("Allowlist proof is required.");
+        return Err(CustomError::NotAllowlisted.into());
+    };
+
+    if !allowlist::verify(root, allowlist::leaf(buyer.key, allowlist.allocation), &allowlist.proof) {
+        msg!("{} with allocation {} isn't in the allowlist.", buyer.key, allowlist.allocation);
+        return Err(CustomError::NotAllowlisted.into());
+    }
+
+    Ok(if allowlist.allocation != 0 { Some(allowlist.allocation) } else { None })
+}
+
+struct Referral<'a, 'b> {
+    destination: &'b AccountInfo<'a>, // referrer wallet or its ATA for the payment mint
+    stats_pda: &'b AccountInfo<'a>,
+    stats: ReferrerStats,
+}
+
+/// Referral accounts (destination, stats PDA) are taken from the remaining accounts first.
+fn load_referral<'a, 'b>(
+    program_id: &Pubkey,
+    state_pda: &AccountInfo<'a>,
+    buyer: &AccountInfo<'a>,
+    referrer: Pubkey,
+    accounts_iter: &mut Iter<'b, AccountInfo<'a>>,
+) -> Result<Referral<'a, 'b>, ProgramError> {
+    if referrer == *buyer.key {
+        msg!("Buyer can't refer themselves.");
+        return Err(CustomError::SelfReferral.into());
+    }
+
+    let destination = next_account_info(accounts_iter)?;
+    let stats_pda = next_account_info(accounts_iter)?;
+
+    let stats = ReferrerStats::verify_and_load(program_id, state_pda, &referrer, stats_pda)?;
+
+    Ok(Referral { destination, stats_pda, stats })
+}
+
+/// The protocol fee goes to the treasury, then the referral share goes to the referrer,
+/// the rest is shared among the price splits and what's left (including rounding dust) goes to the payment account of the price.
+/// Split accounts are taken from the remaining accounts after the referral ones and before tickets, in the order of the price splits.
+/// Returns the total payment.
 fn accept_payment<'a>(
     state: &State,
-    buyer: &AccountInfo<'a>,
-    buyer_ata: &AccountInfo<'a>,
-    payment_ata: &AccountInfo<'a>,
-    spl_program: &AccountInfo<'a>,
+    config: &Config,
+    payment: &Payment<'a, '_>,
     count: u8,
-) -> ProgramResult {
-    let amount = state.find_price(payment_ata)?;
-    let total: u64 = amount * count as u64;
-
-    if !spl_token::check_id(spl_program.key) {
-        msg!("Wrong SPL token program id");
-        return Err(ProgramError::IncorrectProgramId);
-    }
+    params: &BuyParam,
+    referral: Option<&Referral<'a, '_>>,
+    accounts_iter: &mut Iter<AccountInfo<'a>>,
+) -> Result<u64, ProgramError> {
+    let Payment { ref price, payment_ata, payment_mint, treasury, spl_program, .. } = *payment;
+    let amount = price.amount;
+    let total: u64 = amount.checked_mul(count as u64)
+        .ok_or::<ProgramError>(CustomError::WrongPriceOrCount.into())?;
 
     if total < amount {
         msg!("Wrong amount or prize {}", total);
         return Err(CustomError::WrongPriceOrCount.into());
     }
 
-    msg!("Withdrawing payment for {} tickets: {}", count, total);
+    let price_mint = match price.kind {
+        PriceKind::Token => *payment_mint.key,
+        PriceKind::Native => system_program::id(),
+    };
+    if price_mint != params.expected_mint || total > params.max_total {
+        msg!("Expected at most {} of {}, but the price is {} of {}.", params.max_total, params.expected_mint, total, price_mint);
+        return Err(CustomError::PriceChanged.into());
+    }
 
-    invoke_signed(
-        &transfer(
-            spl_program.key,
-            buyer_ata.key,
-            payment_ata.key,
-            buyer.key,
-            &[],
-            total,
-        )?,
-        &[
-            buyer_ata.clone(),
-            payment_ata.clone(),
-            buyer.clone(),
-            spl_program.clone(),
-        ],
-        &[],
-    )?;
+    if price.kind == PriceKind::Token {
+        check_token_program(spl_program)?;
+        config.check_mint(payment_mint.key)?;
+    }
 
-    Ok(())
-}
\ No newline at end of file
+    let mint = if price.kind == PriceKind::Token { Some((payment_mint, spl_program)) } else { None };
+
+    let fee = config.protocol_fee(total);
+    if fee > 0 {
+        config.check_treasury(treasury, mint)?;
+    }
+
+    let mut revenue = total - fee;
+
+    if let Some(referral) = referral {
+        let expected = recipient_for(&referral.stats.referrer, mint);
+        if *referral.destination.key != expected {
+            msg!("Referrer account must be {}.", expected);
+            return Err(CustomError::WrongReferrerAccount.into());
+        }
+
+        let amount = (revenue as u128 * state.referral_bps as u128 / Price::MAX_BPS as u128) as u64;
+        msg!("Referral {} ({} bps) to {}", amount, state.referral_bps, referral.stats.referrer);
+        payment.pay(referral.destination, amount)?;
+        revenue -= amount;
+    }
+
+    let mut rest = revenue;
+    for (split, amount) in price.splits.iter().zip(price.split(revenue)) {
+        let split_account = next_account_info(accounts_iter)?;
+        if *split_account.key != split.account {
+            msg!("Split account must be {}.", split.account);
+            return Err(CustomError::WrongSplitAccount.into());
+        }
+
+        msg!("Split {} ({} bps) to {}", amount, split.bps, split.account);
+        payment.pay(split_account, amount)?;
+        rest -= amount;
+    }
+
+    msg!("Sale of {} tickets for {}: {} to {}, {} to splits, protocol fee {} ({} bps) to {}",
+        count, total, rest, payment_ata.key, revenue - rest, fee, config.fee_bps, treasury.key);
+
+    payment.pay(payment_ata, rest)?;
+    if fee > 0 {
+        payment.pay(treasury, fee)?;
+    }
+
+    Ok(total)
+}
+
+/// The price of the purchase with the accounts it's paid from and to.
+struct Payment<'a, 'b> {
+    price: Price,
+    buyer: &'b AccountInfo<'a>,
+    buyer_ata: &'b AccountInfo<'a>,
+    payment_ata: &'b AccountInfo<'a>,
+    payment_mint: &'b AccountInfo<'a>,
+    treasury: &'b AccountInfo<'a>,
+    system_program: &'b AccountInfo<'a>,
+    spl_program: &'b AccountInfo<'a>,
+}
+
+impl<'a> Payment<'a, '_> {
+    fn pay(&self, destination: &AccountInfo<'a>, amount: u64) -> ProgramResult {
+        if self.price.kind == PriceKind::Native {
+            // buyer_ata, payment_mint and spl_program aren't used, destination is a lamports recipient
+            return invoke(
+                &system_instruction::transfer(self.buyer.key, destination.key, amount),
+                &[self.buyer.clone(), destination.clone(), self.system_program.clone()],
+            );
+        }
+
+        // the destination has to receive the full amount, so the transfer fee (if any) is paid by buyer
+        let amount_with_fee = amount_with_fee(self.payment_mint, amount)?;
+
+        msg!("Transfer {} ({} with fee) to {}", amount, amount_with_fee, destination.key);
+
+        transfer(
+            self.spl_program,
+            self.buyer_ata,
+            self.payment_mint,
+            destination,
+            self.buyer,
+            amount_with_fee,
+            &[],
+        )
+    }
+}
@@ -0,0 +1,101 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::Clock;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::Sysvar;
+
+use crate::error::CustomError;
+use crate::instruction::{CommitOpenParam, ExpireTicketParam};
+use crate::state::State;
+use crate::ticket::Ticket;
+
+/// Fixes the slot (or the VRF request) the ticket randomness is bound to, so it can't be chosen at opening time.
+/// `randomness_account` is either SlotHashes sysvar or a pending VRF request, depending on the state configuration.
+pub fn commit_open<'a>(program_id: &Pubkey,
+                       owner: &AccountInfo<'a>,
+                       state_pda: &AccountInfo<'a>,
+                       ticket_pda: &AccountInfo<'a>,
+                       randomness_account: &AccountInfo<'a>,
+                       params: &CommitOpenParam,
+) -> ProgramResult {
+    if !owner.is_signer {
+        msg!("Owner must be signer.");
+        return Err(CustomError::WrongSigner.into());
+    }
+
+    let mut state = State::verify_and_load(program_id, state_pda, params.lootbox_id, None)?;
+
+    if ticket_pda.owner != program_id || !Ticket::if_initialized(ticket_pda) {
+        msg!("The specified ticket must be initialized.");
+        return Err(CustomError::TicketAccountNotExists.into());
+    }
+
+    let mut ticket = Ticket::load_from(ticket_pda)?;
+    if ticket.owner != *owner.key {
+        msg!("Wrong ticket owner.");
+        return Err(CustomError::WrongTicketOwner.into());
+    }
+    if ticket.state != *state_pda.key {
+        msg!("Ticket was issued by {}, legacy tickets can be only withdrawn.", ticket.state);
+        return Err(CustomError::WrongTicketState.into());
+    }
+    if ticket.commit_slot != 0 {
+        msg!("Ticket was committed at {}.", ticket.commit_slot);
+        return Err(CustomError::TicketAlreadyCommitted.into());
+    }
+
+    ticket.randomness_account = state.randomness.bind(randomness_account, ticket_pda.key)?;
+    ticket.commit_slot = Clock::get()?.slot;
+    msg!("Ticket {} is committed at {} to {}.", ticket.issue_index, ticket.commit_slot, ticket.randomness_account);
+
+    ticket.save_to(ticket_pda)?;
+
+    // the randomness configuration is locked until every committed ticket is opened or expired
+    state.committed_tickets += 1;
+    state.save_to(state_pda)
+}
+
+/// Burns a committed ticket which wasn't opened in time, the owner gets only the rent back.
+/// Anyone can call it, so the owner can't keep a ticket with a known bad roll.
+pub fn expire_ticket<'a>(program_id: &Pubkey,
+                         state_pda: &AccountInfo<'a>,
+                         ticket_pda: &AccountInfo<'a>,
+                         owner: &AccountInfo<'a>,
+                         params: &ExpireTicketParam,
+) -> ProgramResult {
+    let mut state = State::verify_and_load(program_id, state_pda, params.lootbox_id, None)?;
+
+    if ticket_pda.owner != program_id || !Ticket::if_initialized(ticket_pda) {
+        msg!("The specified ticket must be initialized.");
+        return Err(CustomError::TicketAccountNotExists.into());
+    }
+
+    let ticket = Ticket::load_from(ticket_pda)?;
+    if ticket.owner != *owner.key {
+        msg!("Wrong ticket owner.");
+        return Err(CustomError::WrongTicketOwner.into());
+    }
+    if ticket.state != *state_pda.key {
+        msg!("Ticket was issued by {}.", ticket.state);
+        return Err(CustomError::WrongTicketState.into());
+    }
+    if ticket.commit_slot == 0 {
+        msg!("Ticket isn't committed.");
+        return Err(CustomError::TicketNotCommitted.into());
+    }
+
+    let expiry_slot = state.randomness.expiry_slot(ticket.commit_slot);
+    let slot = Clock::get()?.slot;
+    if slot <= expiry_slot {
+        msg!("Ticket can be opened until {}, now {}.", expiry_slot, slot);
+        return Err(CustomError::CommitNotExpired.into());
+    }
+
+    msg!("Ticket {} committed at {} is expired.", ticket.issue_index, ticket.commit_slot);
+    Ticket::close(owner, ticket_pda)?;
+
+    state.committed_tickets = state.committed_tickets.saturating_sub(1);
+    state.withdraw_counter += 1;
+    state.save_to(state_pda)
+}
@@ -0,0 +1,114 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program::invoke_signed;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::create_account;
+use solana_program::sysvar::rent::Rent;
+use solana_program::sysvar::Sysvar;
+
+use crate::config::{Config, ConfigVersion, CONFIG_SEED};
+use crate::error::CustomError;
+use crate::instruction::UpdateConfigParams;
+
+/// Creates the program config, only the upgrade authority of the program can do that.
+pub fn initialize_config<'a>(
+    program_id: &Pubkey,
+    authority: &AccountInfo<'a>,
+    config_pda: &AccountInfo<'a>,
+    program_data: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    if !authority.is_signer {
+        return Err(CustomError::WrongSigner.into());
+    }
+
+    Config::check_upgrade_authority(program_id, authority, program_data)?;
+
+    let (config_pub, bump) = Config::find_address(program_id);
+    if config_pub != *config_pda.key {
+        msg!("Config account {} & generated PDA {} mismatch.", config_pda.key, config_pub);
+        return Err(CustomError::WrongConfig.into());
+    }
+
+    if Config::if_initialized(config_pda) {
+        msg!("Config has been already initialized.");
+        return Err(CustomError::WrongConfig.into());
+    }
+
+    msg!("Create config {}.", config_pub);
+    let lamports = Rent::get()?.minimum_balance(Config::MAX_SIZE);
+
+    invoke_signed(
+        &create_account(
+            authority.key,
+            config_pda.key,
+            lamports,
+            Config::MAX_SIZE as u64,
+            program_id,
+        ),
+        &[authority.clone(), config_pda.clone(), system_program.clone()],
+        &[&[CONFIG_SEED, &[bump]]],
+    )?;
+
+    let config = Config {
+        version: ConfigVersion::Version1,
+        bump,
+        authority: *authority.key,
+        paused: false,
+        fee_bps: 0,
+        treasury: Pubkey::default(),
+        allowed_mints: vec![],
+        max_tickets_per_buy: 0,
+    };
+
+    config.save_to(config_pda)
+}
+
+pub fn update_config<'a>(
+    program_id: &Pubkey,
+    authority: &AccountInfo<'a>,
+    config_pda: &AccountInfo<'a>,
+    params: UpdateConfigParams,
+) -> ProgramResult {
+    let mut config = Config::verify_and_load_owned(program_id, authority, config_pda)?;
+
+    if params.is_paused() {
+        msg!("Update paused from {} to {}.", config.paused, params.paused);
+        config.paused = params.paused;
+    }
+
+    if params.is_fee() {
+        if params.fee_bps > Config::MAX_FEE_BPS {
+            msg!("Fee can't be greater than {} bps.", Config::MAX_FEE_BPS);
+            return Err(CustomError::WrongFee.into());
+        }
+        // otherwise the fee of every purchase would go to an account nobody controls
+        if params.fee_bps > 0 && params.treasury == Pubkey::default() {
+            msg!("Treasury must be set to take a fee.");
+            return Err(CustomError::WrongFee.into());
+        }
+
+        msg!("Update fee from {} bps for {} to {} bps for {}.", config.fee_bps, config.treasury, params.fee_bps, params.treasury);
+        config.fee_bps = params.fee_bps;
+        config.treasury = params.treasury;
+    }
+
+    if params.is_allowed_mints() {
+        if params.allowed_mints.len() > Config::MAX_ALLOWED_MINTS {
+            msg!("Config can't have more than {} allowed mints.", Config::MAX_ALLOWED_MINTS);
+            return Err(CustomError::WrongConfig.into());
+        }
+
+        msg!("Update allowed mints to {:?}.", params.allowed_mints);
+        config.allowed_mints = params.allowed_mints.clone();
+    }
+
+    if params.is_max_tickets_per_buy() {
+        msg!("Update max_tickets_per_buy from {} to {}.", config.max_tickets_per_buy, params.max_tickets_per_buy);
+        config.max_tickets_per_buy = params.max_tickets_per_buy;
+    }
+
+    msg!("Save config.");
+    config.save_to(config_pda)
+}
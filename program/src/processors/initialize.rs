@@ -9,18 +9,36 @@ use solana_program::pubkey::Pubkey;
 use solana_program::system_instruction::create_account;
 use solana_program::sysvar::rent::Rent;
 use solana_program::sysvar::Sysvar;
+use crate::access::Access;
 use crate::error::CustomError;
 use crate::instruction::InitializeParams;
+use crate::message::Action;
+use crate::signer::SignerSet;
 use crate::state::{State, STATE_SEED, StateVersion, VAULT, Price};
 
+#[derive(Clone, Copy)]
+pub struct InitializeAccounts<'a, 'b> {
+    pub admin: &'b AccountInfo<'a>,
+    pub vault_pda: &'b AccountInfo<'a>,
+    pub state_pda: &'b AccountInfo<'a>,
+    pub system_account: &'b AccountInfo<'a>,
+    pub access_pda: &'b AccountInfo<'a>,
+}
+
+/// Remaining accounts are the payment ATAs of `params.prices` in the same order.
 pub fn initialize<'a>(program_id: &Pubkey,
-                      admin: &AccountInfo<'a>,
-                      vault_pda: &AccountInfo<'a>,
-                      state_pda: &AccountInfo<'a>,
-                      system_account: &AccountInfo<'a>,
+                      accounts: &InitializeAccounts<'a, '_>,
                       params: &InitializeParams,
                       accounts_iter: &mut Iter<AccountInfo<'a>>,
 ) -> ProgramResult {
+    let InitializeAccounts {
+        admin,
+        vault_pda,
+        state_pda,
+        system_account,
+        access_pda,
+    } = *accounts;
+
     if !admin.is_signer {
         return Err(CustomError::WrongSigner.into());
     }
@@ -29,6 +47,10 @@ pub fn initialize<'a>(program_id: &Pubkey,
         return Err(CustomError::PdaCannotBeSigner.into());
     }
 
+    // the vault is shared, so after the transfer only the new owner adds lootboxes to it
+    let access = Access::verify_and_load(program_id, admin.key, access_pda)?;
+    access.check_owner(admin)?;
+
     msg!("Creating vault.");
     create_vault(program_id, admin, vault_pda, params.vault_bump, system_account)?;
 
@@ -96,18 +118,29 @@ fn create_state<'a>(program_id: &Pubkey,
         return Err(CustomError::StateAlreadyInitialized.into());
     }
 
+    if params.prices.len() > State::MAX_PRICES {
+        msg!("State can't have more than {} prices.", State::MAX_PRICES);
+        return Err(CustomError::TooManyPrices.into());
+    }
+
     msg!("Build prices set");
     let mut prices: Vec<Price> = Vec::with_capacity(params.prices.len());
-    for amount in &params.prices {
+    for price in &params.prices {
         let account = next_account_info(accounts_iter)?;
+        if prices.iter().any(|x| x.ata == *account.key) {
+            msg!("Price for {} already exists.", account.key);
+            return Err(CustomError::WrongPaymentAta.into());
+        }
         prices.push(Price {
-            amount: *amount,
+            amount: price.amount,
             ata: *account.key,
+            kind: price.kind,
+            splits: vec![],
         });
     }
 
     let state = State {
-        version: StateVersion::Version4,
+        version: StateVersion::Version5,
         id: params.lootbox_id,
         total_supply: 0,
         max_supply: params.max_supply,
@@ -115,11 +148,30 @@ fn create_state<'a>(program_id: &Pubkey,
         end_ts: params.end_ts,
         owner: *admin.key,
         name: params.name.clone(),
-        signer: params.signer,
+        signer_sets: vec![
+            SignerSet::new(Action::Obtain, params.signer.clone()),
+            SignerSet::new(Action::Withdraw, params.signer.clone()),
+        ],
         prices,
         vault_bump: params.vault_bump,
         base_url: params.base_url.clone(),
         withdraw_counter: 0,
+        randomness: params.randomness.clone(),
+        server_seed_hash: None,
+        server_seed: None,
+        legacy_messages_until: 0,
+        authority: *admin.key,
+        paused: 0,
+        referral_bps: 0,
+        referral_registration: false,
+        max_tickets_per_wallet: 0,
+        window_limit: 0,
+        window_duration: 0,
+        allowlist_root: None,
+        phases: vec![],
+        drop_schedule: None,
+        skus: vec![],
+        committed_tickets: 0,
     };
         let lamports = Rent::get()?.minimum_balance(State::MAX_STATE_SIZE);
 
@@ -1,12 +1,19 @@
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::account_info::AccountInfo;
 use solana_program::entrypoint::ProgramResult;
 use solana_program::msg;
+use solana_program::program::invoke;
 use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::transfer;
+use solana_program::sysvar::rent::Rent;
+use solana_program::sysvar::Sysvar;
 
 use crate::error::CustomError;
-use crate::instruction::MigrateToV3Params;
-use crate::state::{State, StateVersion, STATE_SEED};
+use crate::instruction::{MigrateToV3Params, MigrateToV5Params};
+use crate::randomness::{RandomnessConfig, DEFAULT_SLOT_DELAY};
+use crate::message::Action;
+use crate::signer::{Signer, SignerSet};
+use crate::state::{Price, PriceKind, State, StateV4, StateVersion, STATE_SEED};
 
 pub fn migrate_to_v3<'a>(
     program_id: &Pubkey,
@@ -31,27 +38,88 @@ pub fn migrate_to_v3<'a>(
     }
 
     msg!("Get old state.");
-    let old_state = {
+    let mut state = {
         let data = state_pda.data.borrow();
         let mut buf: &[u8] = *data; // there was .deref();
-        State::deserialize(&mut buf)
+        StateV4::deserialize(&mut buf)
     }?;
 
     // TODO: use != Version2 (old)
-    if old_state.version != StateVersion::Version3 {
-        msg!("Wrong state version, expected != {:?} but got {:?}", StateVersion::Version3, old_state.version);
+    if state.version != StateVersion::Version3 {
+        msg!("Wrong state version, expected != {:?} but got {:?}", StateVersion::Version3, state.version);
+        return Err(CustomError::StateWrongVersion.into());
+    }
+
+    if state.owner != *admin.key {
+        msg!("Wrong admin address.");
+        return Err(CustomError::WrongAdminAccount.into())
+    }
+
+    state.version = StateVersion::Version4;
+
+    msg!("Save migrated state.");
+
+    state.serialize(&mut *state_pda.data.borrow_mut())?;
+
+    Ok(())
+}
+
+pub fn migrate_to_v5<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    params: MigrateToV5Params,
+) -> ProgramResult {
+    if !admin.is_signer {
+        return Err(CustomError::WrongSigner.into());
+    }
+
+    let seed = [admin.key.as_ref(), STATE_SEED, &params.lootbox_id.to_be_bytes(), &[params.state_bump]];
+    let state_pub = &Pubkey::create_program_address(&seed, program_id)?;
+
+    if state_pub != state_pda.key {
+        return Err(CustomError::WrongState.into())
+    }
+
+    if !State::if_initialized(state_pda) {
+        msg!("State isn't initialized.");
+        return Err(CustomError::StateNotInitialized.into());
+    }
+
+    if State::get_version(state_pda) != StateVersion::Version4 as u8 {
+        msg!("Wrong state version, expected {:?} but got {}", StateVersion::Version4, State::get_version(state_pda));
         return Err(CustomError::StateWrongVersion.into());
     }
 
+    msg!("Get old state.");
+    let old_state = {
+        let data = state_pda.data.borrow();
+        let mut buf: &[u8] = *data;
+        StateV4::deserialize(&mut buf)
+    }?;
+
     if old_state.owner != *admin.key {
         msg!("Wrong admin address.");
         return Err(CustomError::WrongAdminAccount.into())
     }
 
+    let prices = old_state.prices.iter()
+        .map(|price| Price {
+            amount: price.amount,
+            ata: price.ata,
+            kind: PriceKind::Token,
+            splits: vec![],
+        })
+        .collect();
+
     let state = State {
-        version: StateVersion::Version4,
+        version: StateVersion::Version5,
         id: old_state.id,
-        signer: old_state.signer,
+        signer_sets: vec![
+            SignerSet::new(Action::Obtain, Signer::Secp256k1(old_state.signer)),
+            SignerSet::new(Action::Withdraw, Signer::Secp256k1(old_state.signer)),
+        ],
         max_supply: old_state.max_supply,
         begin_ts: old_state.begin_ts,
         end_ts: old_state.end_ts,
@@ -59,15 +127,42 @@ pub fn migrate_to_v3<'a>(
         total_supply: old_state.total_supply,
         owner: old_state.owner,
         vault_bump: old_state.vault_bump,
-        prices: old_state.prices,
+        prices,
         base_url: old_state.base_url,
-        // first_index: old_state.first_index,
         withdraw_counter: old_state.withdraw_counter,
+        randomness: RandomnessConfig::SlotHashes { delay: DEFAULT_SLOT_DELAY },
+        server_seed_hash: None,
+        server_seed: None,
+        legacy_messages_until: params.legacy_messages_until,
+        authority: old_state.owner,
+        paused: 0,
+        referral_bps: 0,
+        referral_registration: false,
+        max_tickets_per_wallet: 0,
+        window_limit: 0,
+        window_duration: 0,
+        allowlist_root: None,
+        phases: vec![],
+        drop_schedule: None,
+        skus: vec![],
+        committed_tickets: 0,
     };
 
-    msg!("Save migrated state.");
+    if state_pda.data_len() < State::MAX_STATE_SIZE {
+        msg!("Extend state from {} to {} bytes.", state_pda.data_len(), State::MAX_STATE_SIZE);
+        let lamports = Rent::get()?.minimum_balance(State::MAX_STATE_SIZE).saturating_sub(state_pda.lamports());
+        if lamports > 0 {
+            invoke(
+                &transfer(admin.key, state_pda.key, lamports),
+                &[admin.clone(), state_pda.clone(), system_program.clone()],
+            )?;
+        }
+        state_pda.realloc(State::MAX_STATE_SIZE, false)?;
+    }
+
+    msg!("Save migrated state, legacy messages are accepted until {}.", state.legacy_messages_until);
 
     state.save_to(state_pda)?;
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -4,4 +4,16 @@ pub mod migrate;
 pub mod obtain;
 pub mod withdraw;
 pub mod update_state;
-pub mod admin_withdraw;
\ No newline at end of file
+pub mod admin_withdraw;
+pub mod open;
+pub mod set_rewards;
+pub mod commit_open;
+pub mod server_seed;
+pub mod signers;
+pub mod ownership;
+pub mod roles;
+pub mod config;
+pub mod set_price_splits;
+pub mod set_phases;
+pub mod set_skus;
+pub mod register_referrer;
\ No newline at end of file
@@ -1,51 +1,85 @@
+use crate::config::Config;
 use crate::error::CustomError;
 use crate::instruction::ObtainTicketParams;
+use crate::message::{Message, MessageBody};
 use crate::state::{State};
 use crate::verify::verify_signature;
 use solana_program::account_info::AccountInfo;
 use solana_program::clock::Clock;
 use solana_program::sysvar::Sysvar;
 use solana_program::entrypoint::ProgramResult;
-use solana_program::hash::Hasher;
 use solana_program::msg;
 use solana_program::pubkey::Pubkey;
 use crate::ticket::Ticket;
+use crate::user::UserState;
 
-pub fn obtain_ticket<'a>(program_id: &Pubkey,
-                         buyer: &AccountInfo<'a>,
-                         params: ObtainTicketParams,
-                         state_pda: &AccountInfo<'a>,
-                         vault_pda: &AccountInfo<'a>,
-                         ticket_pda: &AccountInfo<'a>,
-                         system_program: &AccountInfo<'a>,
+/// Accounts of the ObtainTicket instruction in the order of the instruction.
+#[derive(Clone, Copy)]
+pub struct ObtainAccounts<'a, 'b> {
+    pub buyer: &'b AccountInfo<'a>,
+    pub state_pda: &'b AccountInfo<'a>,
+    pub vault_pda: &'b AccountInfo<'a>,
+    pub ticket_pda: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub instructions_sysvar: &'b AccountInfo<'a>,
+    pub config_pda: &'b AccountInfo<'a>,
+    pub user_pda: &'b AccountInfo<'a>,
+}
+
+pub fn obtain_ticket(program_id: &Pubkey,
+                     accounts: &ObtainAccounts,
+                     params: ObtainTicketParams,
 ) -> ProgramResult {
+    let ObtainAccounts {
+        buyer,
+        state_pda,
+        vault_pda,
+        ticket_pda,
+        system_program,
+        instructions_sysvar,
+        config_pda,
+        user_pda,
+    } = *accounts;
+
     if !buyer.is_signer {
         msg!("Buyer must be signer.");
         return Err(CustomError::WrongSigner.into());
     }
 
     let mut state = State::verify_and_load(program_id, state_pda, params.lootbox_id, None)?;
+    let config = Config::load(program_id, config_pda)?;
+    state.check_not_paused(&config, State::PAUSE_OBTAIN)?;
 
-    state.check_and_get_correct_count(1)?;
+    let clock = Clock::get()?;
+    state.check_time(&clock)?;
+    state.check_and_get_correct_count(1, None, &clock)?;
     state.check_vault(program_id, vault_pda)?;
-    state.check_time(&Clock::get()?)?;
-
-    let message_hash = {
-        let mut hasher = Hasher::default();
-        // TODO: it will be replaced with server side signature
-        hasher.hash(&vault_pda.key.to_bytes());
-        hasher.hash(&buyer.key.to_bytes());
-        hasher.hash(&params.id.to_be_bytes());
-        hasher.hash(&params.expire_at.to_be_bytes());
-        hasher.result()
+
+    let message = Message {
+        program_id: *program_id,
+        state: *state_pda.key,
+        lootbox_id: params.lootbox_id,
+        expire_at: params.expire_at,
+        body: MessageBody::Obtain {
+            vault: *vault_pda.key,
+            buyer: *buyer.key,
+            id: params.id,
+        },
     };
 
-    verify_signature(&message_hash, params.expire_at, &params.signature, &state)?;
+    verify_signature(&message, &params.signatures, &state, instructions_sysvar)?;
+
+    if state.has_wallet_limits() {
+        let mut user = UserState::load_or_create(program_id, state_pda, buyer, user_pda, system_program)?;
+        user.add_tickets(&state, 1, None, clock.unix_timestamp as u32)?;
+        user.save_to(user_pda)?;
+    }
 
     Ticket::verify_and_create(
         program_id,
         system_program,
         buyer,
+        state_pda.key,
         params.lootbox_id,
         params.id,
         0,
@@ -53,9 +87,10 @@ pub fn obtain_ticket<'a>(program_id: &Pubkey,
         ticket_pda,
         Some(params.bump),
         Some(params.id),
+        0,
     )?;
 
-    state.total_supply += 1;
+    state.add_issued(1, None, &clock);
     state.save_to(state_pda)?;
 
     Ok(())
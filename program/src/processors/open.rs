@@ -0,0 +1,130 @@
+use std::slice::Iter;
+
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::clock::Clock;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::Sysvar;
+
+use crate::config::Config;
+use crate::error::CustomError;
+use crate::instruction::OpenParam;
+use crate::randomness::roll;
+use crate::rewards::RewardTable;
+use crate::state::{State, VAULT};
+use crate::ticket::Ticket;
+use crate::token::{amount_with_fee, create_ata_if_empty, token_program_for, transfer};
+
+/// Accounts of the Open instruction in the order of the instruction, the reward accounts follow them.
+#[derive(Clone, Copy)]
+pub struct OpenAccounts<'a, 'b> {
+    pub owner: &'b AccountInfo<'a>,
+    pub state_pda: &'b AccountInfo<'a>,
+    pub vault_pda: &'b AccountInfo<'a>,
+    pub rewards_pda: &'b AccountInfo<'a>,
+    pub ticket_pda: &'b AccountInfo<'a>,
+    pub randomness_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub spl_program: &'b AccountInfo<'a>,
+    pub token_2022_program: &'b AccountInfo<'a>,
+    pub config_pda: &'b AccountInfo<'a>,
+}
+
+/// Burns the committed ticket and pays out a reward from the on-chain reward table.
+/// `randomness_account` is either SlotHashes sysvar or VRF result, depending on the state configuration.
+/// Remaining accounts are (mint, vault ATA, owner ATA) triples, the one with the chosen mint is used.
+pub fn open<'a>(program_id: &Pubkey,
+                accounts: &OpenAccounts<'a, '_>,
+                params: &OpenParam,
+                accounts_iter: &mut Iter<AccountInfo<'a>>,
+) -> ProgramResult {
+    let OpenAccounts {
+        owner,
+        state_pda,
+        vault_pda,
+        rewards_pda,
+        ticket_pda,
+        randomness_account,
+        system_program,
+        spl_program,
+        token_2022_program,
+        config_pda,
+    } = *accounts;
+
+    if !owner.is_signer {
+        msg!("Receiver must be signer.");
+        return Err(CustomError::WrongSigner.into());
+    }
+
+    let mut state = State::verify_and_load(program_id, state_pda, params.lootbox_id, None)?;
+    let config = Config::load(program_id, config_pda)?;
+    state.check_not_paused(&config, State::PAUSE_OPEN)?;
+
+    let vault_seed = [&state.authority.to_bytes(), VAULT, &[state.vault_bump]];
+
+    state.check_vault_with_seed(program_id, vault_pda, &vault_seed)?;
+
+    let mut table = RewardTable::verify_and_load(program_id, rewards_pda, state_pda)?;
+
+    let ticket = Ticket::verify_and_close(owner, ticket_pda)?;
+    if ticket.state != *state_pda.key {
+        msg!("Ticket was issued by {}, legacy tickets can be only withdrawn.", ticket.state);
+        return Err(CustomError::WrongTicketState.into());
+    }
+
+    if ticket.commit_slot == 0 {
+        msg!("Ticket has to be committed first.");
+        return Err(CustomError::TicketNotCommitted.into());
+    }
+
+    let clock = Clock::get()?;
+    if clock.slot > state.randomness.expiry_slot(ticket.commit_slot) {
+        msg!("Ticket commit at {} has expired.", ticket.commit_slot);
+        return Err(CustomError::RandomnessExpired.into());
+    }
+
+    let randomness = state.randomness
+        .source(randomness_account, &clock)
+        .randomness(ticket_pda.key, &ticket)?;
+    let index = table.pick(roll(&randomness, ticket_pda.key, ticket.issue_index))
+        .ok_or(CustomError::RewardsExhausted)?;
+
+    let entry = &mut table.entries[index];
+    entry.stock -= 1;
+    let (reward_mint, reward_amount) = (entry.mint, entry.amount);
+
+    msg!("Ticket {} won {} of {}.", ticket.issue_index, reward_amount, reward_mint);
+
+    let (token_mint, source_ata, destination_ata) = loop {
+        let token_mint = next_account_info(accounts_iter).map_err(|_| CustomError::RewardAccountsNotFound)?;
+        let source_ata = next_account_info(accounts_iter)?;
+        let destination_ata = next_account_info(accounts_iter)?;
+
+        if *token_mint.key == reward_mint {
+            break (token_mint, source_ata, destination_ata);
+        }
+    };
+    let spl_program = token_program_for(token_mint, &[spl_program, token_2022_program])?;
+
+    create_ata_if_empty(owner, destination_ata, token_mint, system_program, spl_program)?;
+
+    // the receiver gets exactly the amount from the table, the transfer fee (if any) is paid by vault
+    transfer(
+        spl_program,
+        source_ata,
+        token_mint,
+        destination_ata,
+        vault_pda,
+        amount_with_fee(token_mint, reward_amount)?,
+        &[&vault_seed],
+    )?;
+
+    table.save_to(rewards_pda)?;
+
+    state.committed_tickets = state.committed_tickets.saturating_sub(1);
+    state.withdraw_counter += 1;
+    state.save_to(state_pda)?;
+
+    Ok(())
+}
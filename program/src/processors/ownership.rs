@@ -0,0 +1,57 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+
+use crate::access::Access;
+use crate::error::CustomError;
+use crate::instruction::{AcceptOwnerParams, ProposeOwnerParams};
+use crate::state::State;
+
+/// The first step of the ownership transfer, the new owner must accept it.
+/// All lootboxes of the authority share the vault, so they are transferred together through the access account.
+/// State and vault addresses are derived from `State.authority`, so they don't change.
+pub fn propose_owner<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    params: ProposeOwnerParams,
+) -> ProgramResult {
+    let (_, mut access) = State::verify_and_load_owned(program_id, admin, state_pda, access_pda, params.lootbox_id)?;
+
+    msg!("Propose owner {:?} instead of {:?}.", params.new_owner, access.pending_owner);
+    access.pending_owner = params.new_owner;
+    access.create_if_empty(program_id, access_pda, admin, system_program)?;
+    access.save_to(access_pda)?;
+
+    Ok(())
+}
+
+pub fn accept_owner<'a>(
+    program_id: &Pubkey,
+    new_owner: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    params: AcceptOwnerParams,
+) -> ProgramResult {
+    if !new_owner.is_signer {
+        return Err(CustomError::WrongSigner.into());
+    }
+
+    let state = State::verify_and_load(program_id, state_pda, params.lootbox_id, None)?;
+    let mut access = Access::verify_and_load(program_id, &state.authority, access_pda)?;
+
+    if access.pending_owner != Some(*new_owner.key) {
+        msg!("Pending owner is {:?}.", access.pending_owner);
+        return Err(CustomError::WrongPendingOwner.into());
+    }
+
+    msg!("Transfer ownership of the lootboxes of {} from {} to {}.", access.authority, access.owner, new_owner.key);
+    access.owner = *new_owner.key;
+    access.pending_owner = None;
+    access.save_to(access_pda)?;
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+
+use crate::access::Access;
+use crate::error::CustomError;
+use crate::instruction::RegisterReferrerParams;
+use crate::referrer::ReferrerStats;
+use crate::role::RoleGrant;
+use crate::state::State;
+
+#[derive(Clone, Copy)]
+pub struct RegisterReferrerAccounts<'a, 'b> {
+    pub referrer: &'b AccountInfo<'a>,
+    pub approver: &'b AccountInfo<'a>,
+    pub state_pda: &'b AccountInfo<'a>,
+    pub stats_pda: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub access_pda: &'b AccountInfo<'a>,
+}
+
+/// Only registered referrers get the referral share, the referrer pays for the account.
+/// Lootboxes with `referral_registration` also require the approval of the owner or an operator,
+/// otherwise `approver` isn't checked and can be the referrer itself.
+pub fn register_referrer<'a>(
+    program_id: &Pubkey,
+    accounts: &RegisterReferrerAccounts<'a, '_>,
+    params: RegisterReferrerParams,
+) -> ProgramResult {
+    let RegisterReferrerAccounts {
+        referrer,
+        approver,
+        state_pda,
+        stats_pda,
+        system_program,
+        access_pda,
+    } = *accounts;
+
+    if !referrer.is_signer {
+        msg!("Referrer must be signer.");
+        return Err(CustomError::WrongSigner.into());
+    }
+
+    let state = State::verify_and_load(program_id, state_pda, params.lootbox_id, None)?;
+
+    if state.referral_registration {
+        if !approver.is_signer {
+            msg!("Approver must be signer.");
+            return Err(CustomError::WrongSigner.into());
+        }
+        let access = Access::verify_and_load(program_id, &state.authority, access_pda)?;
+        access.check_role(approver.key, RoleGrant::OPERATOR)?;
+    }
+
+    let mut stats = ReferrerStats::load_or_create(program_id, state_pda, referrer.key, stats_pda, referrer, system_program)?;
+
+    msg!("Register referrer {}.", referrer.key);
+    stats.registered = true;
+    stats.save_to(stats_pda)?;
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+use crate::access::Access;
+use crate::error::CustomError;
+use crate::instruction::RoleParams;
+use crate::role::RoleGrant;
+use crate::state::State;
+
+/// Loads the state for instructions which the owner or an account with the role can call.
+pub fn verify_and_load_with_role(
+    program_id: &Pubkey,
+    admin: &AccountInfo,
+    state_pda: &AccountInfo,
+    access_pda: &AccountInfo,
+    lootbox_id: u16,
+    role: u8,
+) -> Result<State, ProgramError> {
+    if !admin.is_signer {
+        msg!("{} must be signer.", admin.key);
+        return Err(CustomError::WrongSigner.into());
+    }
+
+    let state = State::verify_and_load(program_id, state_pda, lootbox_id, None)?;
+    let access = Access::verify_and_load(program_id, &state.authority, access_pda)?;
+
+    access.check_role(admin.key, role)?;
+
+    Ok(state)
+}
+
+/// Roles are granted for all lootboxes of the authority, since they share the vault; the owner pays for the access account.
+pub fn grant_role<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    params: RoleParams,
+) -> ProgramResult {
+    let (_, mut access) = State::verify_and_load_owned(program_id, admin, state_pda, access_pda, params.lootbox_id)?;
+
+    msg!("Grant roles {} to {}.", params.roles, params.account);
+    match access.roles.iter_mut().find(|grant| grant.account == params.account) {
+        Some(grant) => grant.roles |= params.roles,
+        None => {
+            if access.roles.len() >= Access::MAX_ROLES {
+                msg!("Access can't have more than {} accounts with roles.", Access::MAX_ROLES);
+                return Err(CustomError::TooManyRoles.into());
+            }
+            access.roles.push(RoleGrant { account: params.account, roles: params.roles });
+        }
+    }
+
+    access.create_if_empty(program_id, access_pda, admin, system_program)?;
+    access.save_to(access_pda)
+}
+
+/// The account is forgotten once it has no roles left.
+pub fn revoke_role<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    params: RoleParams,
+) -> ProgramResult {
+    let (_, mut access) = State::verify_and_load_owned(program_id, admin, state_pda, access_pda, params.lootbox_id)?;
+
+    if !access.roles.iter().any(|grant| grant.account == params.account) {
+        msg!("{} has no roles.", params.account);
+        return Ok(());
+    }
+
+    msg!("Revoke roles {} from {}.", params.roles, params.account);
+    for grant in access.roles.iter_mut().filter(|grant| grant.account == params.account) {
+        grant.roles &= !params.roles;
+    }
+    access.roles.retain(|grant| grant.roles != 0);
+
+    access.save_to(access_pda)
+}
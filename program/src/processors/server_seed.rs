@@ -0,0 +1,73 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::{Clock, UnixTimestamp};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::hash::hash;
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::Sysvar;
+
+use crate::error::CustomError;
+use crate::instruction::{CommitSeedParams, RevealSeedParams};
+use crate::processors::roles::verify_and_load_with_role;
+use crate::role::RoleGrant;
+use crate::state::State;
+
+/// Stores sha256 of the server seed, the result of every ticket is `randomness::roll(seed, ticket, issue_index)`.
+/// Signed withdraws carry the rolls of their tickets, which are checked against the seed once it's revealed.
+/// The commitment can be set only once and only before the first ticket is issued.
+pub fn commit_seed<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    params: CommitSeedParams,
+) -> ProgramResult {
+    let mut state = verify_and_load_with_role(program_id, admin, state_pda, access_pda, params.lootbox_id, RoleGrant::OPERATOR)?;
+
+    if state.server_seed_hash.is_some() {
+        return Err(CustomError::SeedAlreadyCommitted.into());
+    }
+
+    if state.total_supply > 0 {
+        msg!("{} tickets have been already issued.", state.total_supply);
+        return Err(CustomError::TooLate.into());
+    }
+
+    msg!("Commit server seed hash {:?}.", params.seed_hash);
+    state.server_seed_hash = Some(params.seed_hash);
+    state.save_to(state_pda)?;
+
+    Ok(())
+}
+
+/// Publishes the server seed after the sale, anyone who knows it can do that.
+pub fn reveal_seed<'a>(
+    program_id: &Pubkey,
+    state_pda: &AccountInfo<'a>,
+    params: RevealSeedParams,
+) -> ProgramResult {
+    let mut state = State::verify_and_load(program_id, state_pda, params.lootbox_id, None)?;
+
+    let seed_hash = state.server_seed_hash.ok_or(CustomError::SeedNotCommitted)?;
+
+    if state.server_seed.is_some() {
+        return Err(CustomError::SeedAlreadyRevealed.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    if (state.end_ts as UnixTimestamp) >= now {
+        msg!("too early, now {} is less then end {}", now, state.end_ts);
+        return Err(CustomError::TooEarly.into());
+    }
+
+    if hash(&params.seed).to_bytes() != seed_hash {
+        msg!("Seed hash doesn't match with {:?}.", seed_hash);
+        return Err(CustomError::WrongSeed.into());
+    }
+
+    msg!("Reveal server seed {:?}.", params.seed);
+    state.server_seed = Some(params.seed);
+    state.save_to(state_pda)?;
+
+    Ok(())
+}
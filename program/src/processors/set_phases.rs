@@ -0,0 +1,79 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::{Clock, UnixTimestamp};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::Sysvar;
+
+use crate::error::CustomError;
+use crate::instruction::SetPhasesParams;
+use crate::phase::Phase;
+use crate::processors::roles::verify_and_load_with_role;
+use crate::role::RoleGrant;
+use crate::state::State;
+
+/// Replaces the phases of the sale, an empty list makes the whole sale window a single public phase.
+/// Phases which have already started must be kept with the same schedule, and tickets issued during a phase
+/// are kept by its schedule, so the phase supply can't be reset by the operator.
+pub fn set_phases<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    mut params: SetPhasesParams,
+) -> ProgramResult {
+    let mut state = verify_and_load_with_role(program_id, admin, state_pda, access_pda, params.lootbox_id, RoleGrant::OPERATOR)?;
+
+    if params.phases.len() > State::MAX_PHASES {
+        msg!("State can't have more than {} phases.", State::MAX_PHASES);
+        return Err(CustomError::WrongPhases.into());
+    }
+
+    for (index, phase) in params.phases.iter().enumerate() {
+        if phase.begin_ts > phase.end_ts {
+            msg!("Phase {} begins at {} after its end {}.", index, phase.begin_ts, phase.end_ts);
+            return Err(CustomError::WrongPhases.into());
+        }
+        if phase.begin_ts < state.begin_ts || phase.end_ts > state.end_ts {
+            msg!("Phase {} from {} to {} is outside of the sale from {} to {}.", index, phase.begin_ts, phase.end_ts, state.begin_ts, state.end_ts);
+            return Err(CustomError::WrongPhases.into());
+        }
+        if index > 0 && phase.begin_ts <= params.phases[index - 1].end_ts {
+            msg!("Phase {} begins at {} before the previous one ends.", index, phase.begin_ts);
+            return Err(CustomError::WrongPhases.into());
+        }
+        if phase.price_overrides.len() > Phase::MAX_PRICE_OVERRIDES {
+            msg!("Phase {} has {} price overrides, at most {} are allowed.", index, phase.price_overrides.len(), Phase::MAX_PRICE_OVERRIDES);
+            return Err(CustomError::WrongPhases.into());
+        }
+        if let Some(price_override) = phase.price_overrides.iter().find(|x| !state.prices.iter().any(|price| price.ata == x.ata)) {
+            msg!("Phase {} overrides unknown price {}.", index, price_override.ata);
+            return Err(CustomError::WrongPaymentAta.into());
+        }
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    for current in state.phases.iter().filter(|current| (current.begin_ts as UnixTimestamp) <= now) {
+        let unchanged = params.phases.iter().any(|x| Phase { issued: current.issued, ..x.clone() } == *current);
+        if !unchanged {
+            msg!("Phase from {} to {} has already started and can't be changed.", current.begin_ts, current.end_ts);
+            return Err(CustomError::WrongPhases.into());
+        }
+    }
+
+    for phase in params.phases.iter_mut() {
+        phase.issued = state.phases
+            .iter()
+            .find(|current| current.begin_ts == phase.begin_ts && current.end_ts == phase.end_ts)
+            .map(|current| current.issued)
+            .unwrap_or(0);
+    }
+
+    msg!("Update phases from {:?} to {:?}.", state.phases, params.phases);
+    state.phases = params.phases;
+
+    msg!("Save state.");
+    state.save_to(state_pda)?;
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::{Clock, UnixTimestamp};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::Sysvar;
+
+use crate::error::CustomError;
+use crate::instruction::SetPriceSplitsParams;
+use crate::processors::roles::verify_and_load_with_role;
+use crate::role::RoleGrant;
+use crate::state::Price;
+
+/// Splits can be changed only before the sale starts, so every buyer pays the same recipients.
+pub fn set_price_splits<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    params: SetPriceSplitsParams,
+) -> ProgramResult {
+    let mut state = verify_and_load_with_role(program_id, admin, state_pda, access_pda, params.lootbox_id, RoleGrant::OPERATOR)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if (state.begin_ts as UnixTimestamp) <= now || state.total_supply > 0 {
+        msg!("The sale has already started at {}.", state.begin_ts);
+        return Err(CustomError::TooLate.into());
+    }
+
+    let total_bps: u32 = params.splits.iter().map(|split| split.bps as u32).sum();
+    if params.splits.len() > Price::MAX_SPLITS || total_bps > Price::MAX_BPS as u32 {
+        msg!("{} splits with {} bps in total.", params.splits.len(), total_bps);
+        return Err(CustomError::WrongSplits.into());
+    }
+
+    let price = state.prices
+        .iter_mut()
+        .find(|x| {x.ata == params.price_ata})
+        .ok_or::<ProgramError>(CustomError::WrongPaymentAta.into())?;
+
+    msg!("Update splits for {} from {:?} to {:?}.", params.price_ata, price.splits, params.splits);
+    price.splits = params.splits;
+
+    msg!("Save state.");
+    state.save_to(state_pda)?;
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program::{invoke, invoke_signed};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::{create_account, transfer};
+use solana_program::sysvar::rent::Rent;
+use solana_program::sysvar::Sysvar;
+
+use crate::instruction::SetRewardsParams;
+use crate::processors::roles::verify_and_load_with_role;
+use crate::rewards::{RewardTable, RewardTableVersion, REWARDS_SEED};
+use crate::role::RoleGrant;
+
+pub fn set_rewards<'a>(program_id: &Pubkey,
+                       admin: &AccountInfo<'a>,
+                       state_pda: &AccountInfo<'a>,
+                       access_pda: &AccountInfo<'a>,
+                       rewards_pda: &AccountInfo<'a>,
+                       system_program: &AccountInfo<'a>,
+                       params: SetRewardsParams,
+) -> ProgramResult {
+    verify_and_load_with_role(program_id, admin, state_pda, access_pda, params.lootbox_id, RoleGrant::OPERATOR)?;
+
+    let seed = [state_pda.key.as_ref(), REWARDS_SEED, &[params.rewards_bump]];
+    let rewards_pub = &Pubkey::create_program_address(&seed, program_id)?;
+
+    if rewards_pda.key != rewards_pub {
+        msg!("The reward table key mismatch, seed is {:?}", seed);
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let table = RewardTable {
+        version: RewardTableVersion::Version1,
+        state: *state_pda.key,
+        bump: params.rewards_bump,
+        entries: params.entries,
+    };
+    let space = table.serialized_len()?;
+    let lamports = Rent::get()?.minimum_balance(space);
+
+    if rewards_pda.data_is_empty() {
+        msg!("Create reward table with {} entries.", table.entries.len());
+        invoke_signed(
+            &create_account(
+                admin.key,
+                rewards_pub,
+                lamports,
+                space as u64,
+                program_id,
+            ),
+            &[admin.clone(), rewards_pda.clone(), system_program.clone()],
+            &[
+                &seed
+            ],
+        )?;
+    } else if rewards_pda.data_len() < space {
+        msg!("Extend reward table from {} to {} bytes.", rewards_pda.data_len(), space);
+        let lamports = lamports.saturating_sub(rewards_pda.lamports());
+        if lamports > 0 {
+            invoke(
+                &transfer(admin.key, rewards_pub, lamports),
+                &[admin.clone(), rewards_pda.clone(), system_program.clone()],
+            )?;
+        }
+        rewards_pda.realloc(space, false)?;
+    }
+
+    for entry in &table.entries {
+        msg!("Reward {} of {}: weight {}, stock {}.", entry.amount, entry.mint, entry.weight, entry.stock);
+    }
+
+    table.save_to(rewards_pda)?;
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::{Clock, UnixTimestamp};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::Sysvar;
+
+use crate::error::CustomError;
+use crate::instruction::SetSkusParams;
+use crate::processors::roles::verify_and_load_with_role;
+use crate::role::RoleGrant;
+use crate::sku::Sku;
+use crate::state::{Price, State};
+
+/// Replaces the SKUs of the lootbox, an empty list makes the state prices apply to every purchase.
+/// SKUs can be changed only before the sale starts, so every buyer pays the same prices and splits.
+pub fn set_skus<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    mut params: SetSkusParams,
+) -> ProgramResult {
+    let mut state = verify_and_load_with_role(program_id, admin, state_pda, access_pda, params.lootbox_id, RoleGrant::OPERATOR)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if (state.begin_ts as UnixTimestamp) <= now || state.total_supply > 0 {
+        msg!("The sale has already started at {}.", state.begin_ts);
+        return Err(CustomError::TooLate.into());
+    }
+
+    if params.skus.len() > State::MAX_SKUS {
+        msg!("State can't have more than {} SKUs.", State::MAX_SKUS);
+        return Err(CustomError::WrongSkus.into());
+    }
+
+    for (index, sku) in params.skus.iter().enumerate() {
+        if sku.tier == 0 || params.skus[..index].iter().any(|x| x.tier == sku.tier) {
+            msg!("SKU tier {} is reserved or used twice.", sku.tier);
+            return Err(CustomError::WrongSkus.into());
+        }
+        if sku.prices.len() > Sku::MAX_PRICES {
+            msg!("SKU {} has {} prices, at most {} are allowed.", sku.tier, sku.prices.len(), Sku::MAX_PRICES);
+            return Err(CustomError::WrongSkus.into());
+        }
+        for (price_index, price) in sku.prices.iter().enumerate() {
+            if sku.prices[..price_index].iter().any(|x| x.ata == price.ata) {
+                msg!("SKU {} has two prices for {}.", sku.tier, price.ata);
+                return Err(CustomError::WrongPaymentAta.into());
+            }
+
+            let total_bps: u32 = price.splits.iter().map(|split| split.bps as u32).sum();
+            if price.splits.len() > Price::MAX_SPLITS || total_bps > Price::MAX_BPS as u32 {
+                msg!("{} splits with {} bps in total.", price.splits.len(), total_bps);
+                return Err(CustomError::WrongSplits.into());
+            }
+        }
+    }
+
+    for sku in params.skus.iter_mut() {
+        sku.total_supply = 0;
+    }
+
+    msg!("Update SKUs from {:?} to {:?}.", state.skus, params.skus);
+    state.skus = params.skus;
+
+    msg!("Save state.");
+    state.save_to(state_pda)?;
+
+    Ok(())
+}
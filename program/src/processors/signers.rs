@@ -0,0 +1,106 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+
+use crate::error::CustomError;
+use crate::instruction::{RotateSignerParams, SignerParams};
+use crate::signer::{SignerSet, SignerSlot};
+use crate::state::State;
+
+/// Creates the action signer set on the first signer.
+pub fn add_signer<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    params: SignerParams,
+) -> ProgramResult {
+    let (mut state, _) = State::verify_and_load_owned(program_id, admin, state_pda, access_pda, params.lootbox_id)?;
+
+    if state.signers_count() >= State::MAX_SIGNERS {
+        msg!("State can't have more than {} signers.", State::MAX_SIGNERS);
+        return Err(CustomError::TooManySigners.into());
+    }
+
+    msg!("Add {:?} signer {:?}.", params.action, params.signer);
+    match state.signer_set_mut(params.action) {
+        Some(set) => {
+            if set.signers.iter().any(|slot| slot.contains(&params.signer)) {
+                msg!("Signer {:?} already exists.", params.signer);
+                return Err(CustomError::SignerAlreadyExists.into());
+            }
+            set.signers.push(SignerSlot::new(params.signer.clone()));
+        }
+        None => state.signer_sets.push(SignerSet::new(params.action, params.signer.clone())),
+    }
+
+    save_with_threshold(state, state_pda, &params)
+}
+
+/// Removes the action signer set with the last signer, so the action can't be authorized anymore.
+pub fn remove_signer<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    params: SignerParams,
+) -> ProgramResult {
+    let (mut state, _) = State::verify_and_load_owned(program_id, admin, state_pda, access_pda, params.lootbox_id)?;
+
+    let set = state.signer_set_mut(params.action).ok_or(CustomError::SignerNotFound)?;
+    let index = set.signers.iter()
+        .position(|slot| slot.current == params.signer)
+        .ok_or(CustomError::SignerNotFound)?;
+
+    msg!("Remove {:?} signer {:?}.", params.action, params.signer);
+    set.signers.remove(index);
+
+    if set.signers.is_empty() {
+        msg!("No {:?} signers left.", params.action);
+        state.signer_sets.retain(|set| set.action != params.action);
+        return state.save_to(state_pda);
+    }
+
+    save_with_threshold(state, state_pda, &params)
+}
+
+/// Replaces the key of the slot, the previous key stays valid until `previous_valid_until`.
+/// If the slot has been already rotated, the key before the previous one stops being valid immediately.
+pub fn rotate_signer<'a>(
+    program_id: &Pubkey,
+    admin: &AccountInfo<'a>,
+    state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
+    params: RotateSignerParams,
+) -> ProgramResult {
+    let (mut state, _) = State::verify_and_load_owned(program_id, admin, state_pda, access_pda, params.lootbox_id)?;
+
+    let set = state.signer_set_mut(params.action).ok_or(CustomError::SignerNotFound)?;
+
+    if set.signers.iter().any(|slot| slot.contains(&params.new_signer)) {
+        msg!("Signer {:?} already exists.", params.new_signer);
+        return Err(CustomError::SignerAlreadyExists.into());
+    }
+
+    let slot = set.signers.iter_mut()
+        .find(|slot| slot.current == params.signer)
+        .ok_or(CustomError::SignerNotFound)?;
+
+    msg!("Rotate {:?} signer {:?} to {:?}, the old one is valid until {}.",
+        params.action, params.signer, params.new_signer, params.previous_valid_until);
+    slot.previous = Some(std::mem::replace(&mut slot.current, params.new_signer));
+    slot.previous_valid_until = params.previous_valid_until;
+
+    state.save_to(state_pda)
+}
+
+fn save_with_threshold(mut state: State, state_pda: &AccountInfo, params: &SignerParams) -> ProgramResult {
+    if let Some(set) = state.signer_set_mut(params.action) {
+        msg!("Set {:?} threshold {} of {}.", params.action, params.threshold, set.signers.len());
+        set.threshold = params.threshold;
+    }
+    state.check_thresholds()?;
+
+    state.save_to(state_pda)
+}
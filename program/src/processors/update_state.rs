@@ -1,17 +1,24 @@
 use solana_program::account_info::AccountInfo;
+use solana_program::clock::{Clock, UnixTimestamp};
 use solana_program::entrypoint::ProgramResult;
 use solana_program::msg;
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::Sysvar;
 
+use crate::access::Access;
+use crate::drop_schedule::DropSchedule;
 use crate::error::CustomError;
 use crate::instruction::UpdateStateParams;
-use crate::state::State;
+use crate::role::RoleGrant;
+use crate::state::{Price, State};
+use crate::user::MAX_WINDOW_LIMIT;
 
 pub fn update_state<'a>(
     program_id: &Pubkey,
     admin: &AccountInfo<'a>,
     state_pda: &AccountInfo<'a>,
+    access_pda: &AccountInfo<'a>,
     params: UpdateStateParams,
 ) -> ProgramResult {
     if !admin.is_signer {
@@ -20,10 +27,16 @@ pub fn update_state<'a>(
 
     msg!("Read state.");
     let mut state = State::verify_and_load(program_id, state_pda, params.lootbox_id, Some(params.state_bump))?;
+    let access = Access::verify_and_load(program_id, &state.authority, access_pda)?;
 
-    if state.owner != *admin.key {
-        msg!("Admin doesn't own the state.");
-        return Err(CustomError::WrongAdminAccount.into());
+    // pauser can only move the end of sales to the past and pause more actions
+    let stop_sales = params.is_only_pause()
+        && (!params.is_end_ts() || (params.end_ts as UnixTimestamp) <= Clock::get()?.unix_timestamp)
+        && (!params.is_paused() || (params.paused & state.paused) == state.paused);
+    let allowed = access.has_role(admin.key, RoleGrant::OPERATOR) || (stop_sales && access.has_role(admin.key, RoleGrant::PAUSER));
+    if !allowed {
+        msg!("{} isn't allowed to update the state.", admin.key);
+        return Err(CustomError::MissingRole.into());
     }
 
     if params.is_max_supply() {
@@ -47,6 +60,96 @@ pub fn update_state<'a>(
         msg!("Update price for token ATA {} from {} to {}.", params.price_ata, price.amount, params.price_amount);
         price.amount = params.price_amount;
     }
+    if params.is_new_price() {
+        if state.prices.iter().any(|x| {x.ata == params.price_ata}) {
+            msg!("Price for {} already exists.", params.price_ata);
+            return Err(CustomError::WrongPaymentAta.into());
+        }
+        if state.prices.len() >= State::MAX_PRICES {
+            msg!("State can't have more than {} prices.", State::MAX_PRICES);
+            return Err(CustomError::TooManyPrices.into());
+        }
+
+        msg!("Add {:?} price {} for {}.", params.price_kind, params.price_amount, params.price_ata);
+        state.prices.push(Price {
+            amount: params.price_amount,
+            ata: params.price_ata,
+            kind: params.price_kind,
+            splits: vec![],
+        });
+    }
+
+    if params.is_randomness() {
+        if state.committed_tickets > 0 {
+            msg!("{} tickets are committed to the current randomness.", state.committed_tickets);
+            return Err(CustomError::TicketsCommitted.into());
+        }
+
+        msg!("Update randomness from {:?} to {:?}.", state.randomness, params.randomness);
+        state.randomness = params.randomness.clone();
+    }
+
+    if params.is_legacy_messages_until() {
+        msg!("Update legacy_messages_until from {} to {}.", state.legacy_messages_until, params.legacy_messages_until);
+        state.legacy_messages_until = params.legacy_messages_until;
+    }
+
+    if params.is_paused() {
+        msg!("Update paused from {} to {}.", state.paused, params.paused);
+        state.paused = params.paused;
+    }
+
+    if params.is_referral() {
+        if params.referral_bps > Price::MAX_BPS {
+            msg!("Referral share can't be greater than {} bps.", Price::MAX_BPS);
+            return Err(CustomError::WrongFee.into());
+        }
+
+        msg!("Update referral from {} bps to {} bps, registration required {}.",
+            state.referral_bps, params.referral_bps, params.referral_registration);
+        state.referral_bps = params.referral_bps;
+        state.referral_registration = params.referral_registration;
+    }
+
+    if params.is_wallet_limits() {
+        if params.window_duration != 0 && (params.window_limit == 0 || params.window_limit as usize > MAX_WINDOW_LIMIT) {
+            msg!("Window limit must be from 1 to {}.", MAX_WINDOW_LIMIT);
+            return Err(CustomError::WrongWalletLimits.into());
+        }
+
+        msg!("Update wallet limits from {} and {} per {} seconds to {} and {} per {} seconds.",
+            state.max_tickets_per_wallet, state.window_limit, state.window_duration,
+            params.max_tickets_per_wallet, params.window_limit, params.window_duration);
+        state.max_tickets_per_wallet = params.max_tickets_per_wallet;
+        state.window_limit = params.window_limit;
+        state.window_duration = params.window_duration;
+    }
+
+    if params.is_allowlist() {
+        msg!("Update allowlist root from {:?} to {:?}.", state.allowlist_root, params.allowlist_root);
+        state.allowlist_root = params.allowlist_root;
+    }
+
+    if params.is_drop_schedule() {
+        let drop_schedule = if params.drop_period == 0 {
+            None
+        } else {
+            let mut drop_schedule = DropSchedule::new(params.drop_start_ts, params.drop_period, params.drop_duration, params.drop_supply);
+            if !drop_schedule.is_valid() {
+                msg!("Drop window of {} seconds every {} seconds with {} tickets.", params.drop_duration, params.drop_period, params.drop_supply);
+                return Err(CustomError::WrongDropSchedule.into());
+            }
+            // tickets sold in the current window still count
+            if let Some(current) = &state.drop_schedule {
+                drop_schedule.window_start = current.window_start;
+                drop_schedule.issued = current.issued;
+            }
+            Some(drop_schedule)
+        };
+
+        msg!("Update drop schedule from {:?} to {:?}.", state.drop_schedule, drop_schedule);
+        state.drop_schedule = drop_schedule;
+    }
 
     msg!("Save state.");
     state.save_to(state_pda)?;
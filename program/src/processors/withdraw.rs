@@ -2,52 +2,94 @@ use std::slice::Iter;
 
 use solana_program::account_info::{next_account_info, AccountInfo};
 use solana_program::entrypoint::ProgramResult;
-use solana_program::hash::Hasher;
 use solana_program::msg;
-use solana_program::program::invoke_signed;
 use solana_program::pubkey::Pubkey;
-use spl_token::instruction::transfer;
-use spl_associated_token_account::instruction::create_associated_token_account;
 
+use crate::config::Config;
 use crate::error::CustomError;
 use crate::instruction::WithdrawParam;
+use crate::message::{Message, MessageBody, Reward};
+use crate::randomness::roll;
 use crate::state::{State, VAULT};
 use crate::ticket::Ticket;
+use crate::token::{amount_with_fee, create_ata_if_empty, token_program_for, transfer};
+use crate::user::UserState;
 use crate::verify::verify_signature;
 
+#[derive(Clone, Copy)]
+pub struct WithdrawAccounts<'a, 'b> {
+    pub owner: &'b AccountInfo<'a>,
+    pub vault_pda: &'b AccountInfo<'a>,
+    pub state_pda: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub spl_program: &'b AccountInfo<'a>,
+    pub token_2022_program: &'b AccountInfo<'a>,
+    pub user_pda: &'b AccountInfo<'a>,
+    pub instructions_sysvar: &'b AccountInfo<'a>,
+    pub config_pda: &'b AccountInfo<'a>,
+}
+
 pub fn withdraw<'a>(program_id: &Pubkey,
-                    owner: &AccountInfo<'a>,
+                    accounts: &WithdrawAccounts<'a, '_>,
                     params: &WithdrawParam,
-                    state_pda: &AccountInfo<'a>,
-                    vault_pda: &AccountInfo<'a>,
-                    system_program: &AccountInfo<'a>,
-                    spl_program: &AccountInfo<'a>,
                     accounts_iter: &mut Iter<AccountInfo<'a>>,
 ) -> ProgramResult {
+    let WithdrawAccounts {
+        owner,
+        vault_pda,
+        state_pda,
+        system_program,
+        user_pda,
+        instructions_sysvar,
+        config_pda,
+        ..
+    } = *accounts;
+
     if !owner.is_signer {
         msg!("Receiver must be signer.");
         return Err(CustomError::WrongSigner.into());
     }
 
     let mut state = State::verify_and_load(program_id, state_pda, params.lootbox_id, None)?;
+    let config = Config::load(program_id, config_pda)?;
+    state.check_not_paused(&config, State::PAUSE_WITHDRAW)?;
 
-    let vault_seed = [&state.owner.to_bytes(), VAULT, &[state.vault_bump]];
+    let vault_seed = [&state.authority.to_bytes(), VAULT, &[state.vault_bump]];
 
     state.check_vault_with_seed(program_id, vault_pda, &vault_seed)?;
 
-    let mut hasher = Hasher::default();
-
-    burn_tickets(owner, params.tickets, accounts_iter, &mut hasher)?;
-    transfer_tokens(owner, &params.amounts, accounts_iter, &mut hasher, vault_pda, system_program, spl_program, &vault_seed)?;
-
-    // TODO: think is it good idea, maybe state is better, because the same vault might be used for multiple lootboxes
-    hasher.hash(&vault_pda.key.to_bytes());
-    hasher.hash(&owner.key.to_bytes());
-    hasher.hash(&params.expire_at.to_be_bytes());
-
-    let message_hash = hasher.result();
-
-    verify_signature(&message_hash, params.expire_at, &params.signature, &state)?;
+    // the signature is verified below, the whole transaction fails otherwise
+    let mut user = UserState::load_or_create(program_id, state_pda, owner, user_pda, system_program)?;
+    user.use_withdraw_nonce(params.nonce)?;
+
+    let mut tickets = Vec::with_capacity(params.tickets as usize);
+    let mut tiers = Vec::with_capacity(params.tickets as usize);
+    let mut issue_indexes = Vec::with_capacity(params.tickets as usize);
+    let mut rewards = Vec::with_capacity(params.amounts.len());
+
+    burn_tickets(owner, params.tickets, accounts_iter, &mut tickets, &mut tiers, &mut issue_indexes)?;
+    check_rolls(&state, &tickets, &issue_indexes, &params.rolls)?;
+    transfer_tokens(accounts, &params.amounts, accounts_iter, &mut rewards, &vault_seed)?;
+
+    let message = Message {
+        program_id: *program_id,
+        state: *state_pda.key,
+        lootbox_id: params.lootbox_id,
+        expire_at: params.expire_at,
+        body: MessageBody::Withdraw {
+            tickets,
+            tiers,
+            rolls: params.rolls.clone(),
+            rewards,
+            vault: *vault_pda.key,
+            owner: *owner.key,
+            nonce: params.nonce,
+        },
+    };
+
+    verify_signature(&message, &params.signatures, &state, instructions_sysvar)?;
+
+    user.save_to(user_pda)?;
 
     state.withdraw_counter += params.tickets as u32;
     state.save_to(state_pda)?;
@@ -58,70 +100,82 @@ pub fn withdraw<'a>(program_id: &Pubkey,
 fn burn_tickets<'a>(owner: &AccountInfo<'a>,
                     count: u8,
                     accounts_iter: &mut Iter<AccountInfo<'a>>,
-                    hasher: &mut Hasher,
+                    tickets: &mut Vec<Pubkey>,
+                    tiers: &mut Vec<u8>,
+                    issue_indexes: &mut Vec<u32>,
 ) -> ProgramResult {
     for _ in 0..count {
         let ticket_pda = next_account_info(accounts_iter)?;
-        hasher.hash(&ticket_pda.key.to_bytes());
+        tickets.push(*ticket_pda.key);
 
-        Ticket::verify_and_close(owner, ticket_pda)?;
+        let ticket = Ticket::verify_and_close(owner, ticket_pda)?;
+        if ticket.commit_slot != 0 {
+            // its roll may be already known, so it's either opened or expired
+            msg!("Ticket {} was committed at {}.", ticket_pda.key, ticket.commit_slot);
+            return Err(CustomError::TicketAlreadyCommitted.into());
+        }
+        tiers.push(ticket.tier);
+        issue_indexes.push(ticket.issue_index);
     }
 
     Ok(())
 }
 
-fn transfer_tokens<'a>(owner: &AccountInfo<'a>,
+/// While the server seed is committed the backend signs the roll of every ticket, so its rewards can't be chosen afterwards.
+/// Once the seed is revealed the rolls are checked here, the earlier ones can be recomputed by anyone.
+fn check_rolls(state: &State, tickets: &[Pubkey], issue_indexes: &[u32], rolls: &[u64]) -> ProgramResult {
+    if state.server_seed_hash.is_none() {
+        if !rolls.is_empty() {
+            msg!("Server seed isn't committed, but {} rolls are specified.", rolls.len());
+            return Err(CustomError::WrongRolls.into());
+        }
+        return Ok(());
+    }
+
+    if rolls.len() != tickets.len() {
+        msg!("{} rolls are specified for {} tickets.", rolls.len(), tickets.len());
+        return Err(CustomError::WrongRolls.into());
+    }
+
+    if let Some(seed) = &state.server_seed {
+        for ((ticket, issue_index), expected) in tickets.iter().zip(issue_indexes).zip(rolls) {
+            if roll(seed, ticket, *issue_index) != *expected {
+                msg!("Roll of ticket {} doesn't match with the server seed.", ticket);
+                return Err(CustomError::WrongRolls.into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn transfer_tokens<'a>(accounts: &WithdrawAccounts<'a, '_>,
                        amounts: &Vec<u64>,
                        accounts_iter: &mut Iter<AccountInfo<'a>>,
-                       hasher: &mut Hasher,
-                       vault_pda: &AccountInfo<'a>,
-                       system_program: &AccountInfo<'a>,
-                       spl_program: &AccountInfo<'a>,
+                       rewards: &mut Vec<Reward>,
                        seed: &[&[u8]],
 ) -> ProgramResult {
+    let WithdrawAccounts { owner, vault_pda, system_program, spl_program, token_2022_program, .. } = *accounts;
+    let token_programs = [spl_program, token_2022_program];
+
     for amount in amounts {
         let token_mint = next_account_info(accounts_iter)?;
-        hasher.hash(&token_mint.key.to_bytes());
-        hasher.hash(&amount.to_be_bytes());
+        rewards.push(Reward { mint: *token_mint.key, amount: *amount });
 
         let source_ata = next_account_info(accounts_iter)?;
         let destination_ata = next_account_info(accounts_iter)?;
-
-        if destination_ata.data_is_empty() {
-            invoke_signed(
-                &create_associated_token_account(
-                    destination_ata.key,
-                    owner.key,
-                    token_mint.key,
-                    spl_program.key,
-                ),
-                &[
-                    owner.clone(),
-                    destination_ata.clone(),
-                    owner.clone(),
-                    token_mint.clone(),
-                    system_program.clone(),
-                    spl_program.clone(),
-                ],
-                &[],
-            )?;
-        }
-
-        invoke_signed(
-            &transfer(
-                spl_program.key,
-                source_ata.key,
-                destination_ata.key,
-                vault_pda.key,
-                &[],
-                *amount,
-            )?,
-            &[
-                source_ata.clone(),
-                destination_ata.clone(),
-                vault_pda.clone(),
-                spl_program.clone(),
-            ],
+        let spl_program = token_program_for(token_mint, &token_programs)?;
+
+        create_ata_if_empty(owner, destination_ata, token_mint, system_program, spl_program)?;
+
+        // the receiver gets exactly the signed amount, the transfer fee (if any) is paid by vault
+        transfer(
+            spl_program,
+            source_ata,
+            token_mint,
+            destination_ata,
+            vault_pda,
+            amount_with_fee(token_mint, *amount)?,
             &[seed],
         )?;
     }
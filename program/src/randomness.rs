@@ -0,0 +1,298 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::Clock;
+use solana_program::hash::Hasher;
+use solana_program::msg;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::slot_hashes;
+use std::cell::Ref;
+
+use crate::error::CustomError;
+use crate::ticket::Ticket;
+
+pub const DEFAULT_SLOT_DELAY: u16 = 2;
+// about a day, the oracle result stays in the account, so it's only a limit for the owner to open the ticket
+pub const VRF_EXPIRY_SLOTS: u64 = 216_000;
+
+const SLOT_HASHES_HEADER: usize = 8;
+const SLOT_HASHES_ENTRY: usize = 8 + 32;
+
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub enum RandomnessConfig {
+    // hash of the slot which comes `delay` slots after the ticket commit
+    SlotHashes { delay: u16 },
+    // result account written by an oracle program
+    Vrf(VrfConfig),
+}
+
+/// Layout of a VRF result account, all offsets are in bytes from the beginning of the account data.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct VrfConfig {
+    pub oracle_program: Pubkey,
+    pub seed_offset: u16, // 32 bytes, must be equal to the ticket address
+    pub slot_offset: u16, // u64 LE, the slot the randomness was requested at
+    pub result_offset: u16, // 32 bytes, all zeros until fulfilled, the request must be pending at the commit
+}
+
+pub trait RandomnessSource {
+    /// Returns 32 random bytes for the committed ticket.
+    fn randomness(&self, ticket_pda: &Pubkey, ticket: &Ticket) -> Result<[u8; 32], ProgramError>;
+}
+
+pub struct SlotHashesRandomness<'a, 'b> {
+    pub slot_hashes: &'b AccountInfo<'a>,
+    pub delay: u16,
+    pub current_slot: u64,
+}
+
+pub struct VrfRandomness<'a, 'b> {
+    pub result_account: &'b AccountInfo<'a>,
+    pub config: &'b VrfConfig,
+}
+
+impl RandomnessConfig {
+    /// The last slot a ticket committed at `commit_slot` can be opened at, after it the ticket can be only expired.
+    /// Otherwise the owner could keep a ticket with a known bad roll and get it paid by a signed withdraw.
+    pub fn expiry_slot(&self, commit_slot: u64) -> u64 {
+        match self {
+            RandomnessConfig::SlotHashes { delay } => commit_slot + *delay as u64 + solana_program::slot_hashes::MAX_ENTRIES as u64,
+            RandomnessConfig::Vrf(_) => commit_slot + VRF_EXPIRY_SLOTS,
+        }
+    }
+
+    /// Checks the randomness account at the commit and returns the key the ticket is bound to.
+    /// A VRF request must be pending, so the owner can't commit to one with a known result;
+    /// the ticket can be opened only with this request then.
+    pub fn bind(&self, account: &AccountInfo, ticket_pda: &Pubkey) -> Result<Pubkey, ProgramError> {
+        match self {
+            RandomnessConfig::SlotHashes { .. } => Ok(Pubkey::default()),
+            RandomnessConfig::Vrf(config) => {
+                let data = config.check_request(account, ticket_pda)?;
+                if read_bytes(&data, config.result_offset as usize)? != [0u8; 32] {
+                    msg!("VRF request {} is already fulfilled.", account.key);
+                    return Err(CustomError::WrongRandomnessAccount.into());
+                }
+
+                Ok(*account.key)
+            }
+        }
+    }
+
+    pub fn source<'a, 'b>(&'b self, account: &'b AccountInfo<'a>, clock: &Clock) -> Box<dyn RandomnessSource + 'b> {
+        match self {
+            RandomnessConfig::SlotHashes { delay } => Box::new(SlotHashesRandomness {
+                slot_hashes: account,
+                delay: *delay,
+                current_slot: clock.slot,
+            }),
+            RandomnessConfig::Vrf(config) => Box::new(VrfRandomness {
+                result_account: account,
+                config,
+            }),
+        }
+    }
+}
+
+impl<'a, 'b> RandomnessSource for SlotHashesRandomness<'a, 'b> {
+    fn randomness(&self, _ticket_pda: &Pubkey, ticket: &Ticket) -> Result<[u8; 32], ProgramError> {
+        if !slot_hashes::check_id(self.slot_hashes.key) {
+            msg!("SlotHashes sysvar was expected.");
+            return Err(CustomError::WrongRandomnessAccount.into());
+        }
+
+        let target = ticket.commit_slot + self.delay as u64;
+        if self.current_slot <= target {
+            msg!("Slot {} hash isn't available at {}.", target, self.current_slot);
+            return Err(CustomError::RandomnessNotReady.into());
+        }
+
+        // u64 number of entries, then (u64 slot, 32 bytes hash) pairs, the most recent goes first;
+        // skipped slots aren't there, so the first slot at or after the target one is used
+        let data = self.slot_hashes.data.borrow();
+        let count = read_u64(&data, 0)? as usize;
+        let mut candidate = None;
+        let mut reached = false;
+        for index in 0..count {
+            let offset = SLOT_HASHES_HEADER + index * SLOT_HASHES_ENTRY;
+            let slot = read_u64(&data, offset)?;
+            if slot < target {
+                reached = true;
+                break;
+            }
+            candidate = Some(read_bytes(&data, offset + 8)?);
+            if slot == target {
+                reached = true;
+                break;
+            }
+        }
+
+        if !reached {
+            msg!("Slot {} is out of SlotHashes, the ticket can be only expired.", target);
+            return Err(CustomError::RandomnessExpired.into());
+        }
+
+        candidate.ok_or_else(|| CustomError::RandomnessNotReady.into())
+    }
+}
+
+impl VrfConfig {
+    /// The request must be made by the oracle for the ticket.
+    fn check_request<'a, 'b>(&self, account: &'b AccountInfo<'a>, ticket_pda: &Pubkey) -> Result<Ref<'b, &'a mut [u8]>, ProgramError> {
+        if *account.owner != self.oracle_program {
+            msg!("VRF account must be owned by {}.", self.oracle_program);
+            return Err(CustomError::WrongRandomnessAccount.into());
+        }
+
+        let data = account.data.borrow();
+
+        if read_bytes(&data, self.seed_offset as usize)? != ticket_pda.to_bytes() {
+            msg!("VRF account was requested for another ticket.");
+            return Err(CustomError::WrongRandomnessAccount.into());
+        }
+
+        let request_slot = read_u64(&data, self.slot_offset as usize)?;
+        msg!("VRF was requested at {}.", request_slot);
+
+        Ok(data)
+    }
+}
+
+impl<'a, 'b> RandomnessSource for VrfRandomness<'a, 'b> {
+    fn randomness(&self, ticket_pda: &Pubkey, ticket: &Ticket) -> Result<[u8; 32], ProgramError> {
+        if *self.result_account.key != ticket.randomness_account {
+            msg!("Ticket is bound to VRF request {}.", ticket.randomness_account);
+            return Err(CustomError::WrongRandomnessAccount.into());
+        }
+
+        let data = self.config.check_request(self.result_account, ticket_pda)?;
+
+        let result = read_bytes(&data, self.config.result_offset as usize)?;
+        if result == [0u8; 32] {
+            msg!("VRF request isn't fulfilled yet.");
+            return Err(CustomError::RandomnessNotReady.into());
+        }
+
+        Ok(result)
+    }
+}
+
+/// Mixes randomness with the ticket, so every ticket gets its own number.
+pub fn roll(randomness: &[u8; 32], ticket_pda: &Pubkey, issue_index: u32) -> u64 {
+    let mut hasher = Hasher::default();
+    hasher.hash(randomness);
+    hasher.hash(&ticket_pda.to_bytes());
+    hasher.hash(&issue_index.to_be_bytes());
+
+    let mut random = [0u8; 8];
+    random.copy_from_slice(&hasher.result().to_bytes()[..8]);
+
+    u64::from_le_bytes(random)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, ProgramError> {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(data.get(offset..offset + 8).ok_or(ProgramError::InvalidAccountData)?);
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_bytes(data: &[u8], offset: usize) -> Result<[u8; 32], ProgramError> {
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(data.get(offset..offset + 32).ok_or(ProgramError::InvalidAccountData)?);
+    Ok(buf)
+}
+
+#[test]
+fn test_slot_hashes_randomness() {
+    let ticket = |commit_slot: u64| Ticket {
+        prefix: *crate::ticket::TICKET_PREFIX,
+        version: Ticket::get_last_version(),
+        owner: Pubkey::new_unique(),
+        lootbox_id: 1,
+        issue_index: 0,
+        external_id: 0,
+        state: Pubkey::new_unique(),
+        commit_slot,
+        tier: 0,
+        randomness_account: Pubkey::default(),
+    };
+
+    // slots 105, 104, 102 (103 was skipped), 101
+    let mut data = 4u64.to_le_bytes().to_vec();
+    for slot in [105u64, 104, 102, 101] {
+        data.extend_from_slice(&slot.to_le_bytes());
+        data.extend_from_slice(&[slot as u8; 32]);
+    }
+    let key = slot_hashes::id();
+    let owner = solana_program::sysvar::id();
+    let mut lamports = 0;
+    let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+    let source = |delay: u16| SlotHashesRandomness { slot_hashes: &account, delay, current_slot: 106 };
+    let ticket_pda = Pubkey::new_unique();
+
+    assert_eq!(source(2).randomness(&ticket_pda, &ticket(100)).unwrap(), [102u8; 32]);
+    assert_eq!(source(3).randomness(&ticket_pda, &ticket(100)).unwrap(), [104u8; 32]);
+    assert_eq!(source(6).randomness(&ticket_pda, &ticket(100)), Err(CustomError::RandomnessNotReady.into()));
+    assert_eq!(source(0).randomness(&ticket_pda, &ticket(100)), Err(CustomError::RandomnessExpired.into()));
+}
+
+#[test]
+fn test_expiry_slot() {
+    assert_eq!(RandomnessConfig::SlotHashes { delay: 2 }.expiry_slot(100), 614);
+
+    let vrf = RandomnessConfig::Vrf(VrfConfig {
+        oracle_program: Pubkey::new_unique(),
+        seed_offset: 0,
+        slot_offset: 32,
+        result_offset: 40,
+    });
+    assert_eq!(vrf.expiry_slot(100), 100 + VRF_EXPIRY_SLOTS);
+}
+
+#[test]
+fn test_vrf_binding() {
+    let oracle_program = Pubkey::new_unique();
+    let config = RandomnessConfig::Vrf(VrfConfig { oracle_program, seed_offset: 0, slot_offset: 32, result_offset: 40 });
+    let ticket_pda = Pubkey::new_unique();
+    let request = |result: u8| {
+        let mut data = ticket_pda.to_bytes().to_vec();
+        data.extend_from_slice(&100u64.to_le_bytes());
+        data.extend_from_slice(&[result; 32]);
+        data
+    };
+
+    let (key, other_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut lamports, mut other_lamports) = (0, 0);
+    let (mut data, mut other_data) = (request(0), request(0));
+    let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &oracle_program, false, 0);
+    let other = AccountInfo::new(&other_key, false, false, &mut other_lamports, &mut other_data, &oracle_program, false, 0);
+
+    // the pending request is bound at the commit
+    assert_eq!(config.bind(&account, &ticket_pda).unwrap(), key);
+    assert_eq!(config.bind(&account, &Pubkey::new_unique()), Err(CustomError::WrongRandomnessAccount.into()));
+
+    let mut ticket = Ticket {
+        prefix: *crate::ticket::TICKET_PREFIX,
+        version: Ticket::get_last_version(),
+        owner: Pubkey::new_unique(),
+        lootbox_id: 1,
+        issue_index: 0,
+        external_id: 0,
+        state: Pubkey::new_unique(),
+        commit_slot: 101,
+        tier: 0,
+        randomness_account: key,
+    };
+    let clock = Clock::default();
+    assert_eq!(config.source(&account, &clock).randomness(&ticket_pda, &ticket), Err(CustomError::RandomnessNotReady.into()));
+
+    // once fulfilled, it can't be bound anymore, and only the bound request opens the ticket
+    account.data.borrow_mut()[40..].copy_from_slice(&[7; 32]);
+    other.data.borrow_mut()[40..].copy_from_slice(&[9; 32]);
+    assert_eq!(config.bind(&account, &ticket_pda), Err(CustomError::WrongRandomnessAccount.into()));
+    assert_eq!(config.source(&account, &clock).randomness(&ticket_pda, &ticket).unwrap(), [7; 32]);
+    assert_eq!(config.source(&other, &clock).randomness(&ticket_pda, &ticket), Err(CustomError::WrongRandomnessAccount.into()));
+
+    ticket.randomness_account = Pubkey::default();
+    assert_eq!(config.source(&account, &clock).randomness(&ticket_pda, &ticket), Err(CustomError::WrongRandomnessAccount.into()));
+}
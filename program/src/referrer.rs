@@ -0,0 +1,132 @@
+use crate::error::CustomError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program::invoke_signed;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::create_account;
+use solana_program::sysvar::rent::Rent;
+use solana_program::sysvar::Sysvar;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+
+pub const REFERRER_SEED: &[u8] = b"referrer";
+
+/// Per (state, referrer) stats, created by the registration, only registered referrers get the referral share.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ReferrerStats {
+    pub version: ReferrerStatsVersion,
+    pub state: Pubkey,
+    pub referrer: Pubkey,
+    pub bump: u8,
+    pub registered: bool,
+    pub purchases: u32,
+    pub tickets: u32,
+    pub volume: u64, // sum of referred payments in units of their price, prices in different mints are summed up as is
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum ReferrerStatsVersion {
+    Version1 = 1,
+}
+
+impl ReferrerStats {
+    pub const SIZE: usize = size_of::<ReferrerStats>();
+
+    /// `payer` pays for the account if it doesn't exist yet.
+    pub fn load_or_create<'a>(program_id: &Pubkey,
+                              state_pda: &AccountInfo<'a>,
+                              referrer: &Pubkey,
+                              stats_pda: &AccountInfo<'a>,
+                              payer: &AccountInfo<'a>,
+                              system_program: &AccountInfo<'a>,
+    ) -> Result<ReferrerStats, ProgramError> {
+        let bump = Self::check_pda(program_id, state_pda, referrer, stats_pda)?;
+
+        if Self::if_initialized(stats_pda) {
+            return Self::load_from(stats_pda);
+        }
+
+        msg!("Create referrer account {}.", stats_pda.key);
+        let lamports = Rent::get()?.minimum_balance(Self::SIZE);
+
+        invoke_signed(
+            &create_account(
+                payer.key,
+                stats_pda.key,
+                lamports,
+                Self::SIZE as u64,
+                program_id,
+            ),
+            &[payer.clone(), stats_pda.clone(), system_program.clone()],
+            &[&[state_pda.key.as_ref(), REFERRER_SEED, referrer.as_ref(), &[bump]]],
+        )?;
+
+        Ok(ReferrerStats {
+            version: ReferrerStatsVersion::Version1,
+            state: *state_pda.key,
+            referrer: *referrer,
+            bump,
+            registered: false,
+            purchases: 0,
+            tickets: 0,
+            volume: 0,
+        })
+    }
+
+    /// The referrer must have been registered, so buyers never pay for the stats account.
+    pub fn verify_and_load(program_id: &Pubkey,
+                           state_pda: &AccountInfo,
+                           referrer: &Pubkey,
+                           stats_pda: &AccountInfo,
+    ) -> Result<ReferrerStats, ProgramError> {
+        Self::check_pda(program_id, state_pda, referrer, stats_pda)?;
+
+        let stats = if Self::if_initialized(stats_pda) { Some(Self::load_from(stats_pda)?) } else { None };
+        match stats {
+            Some(stats) if stats.registered => Ok(stats),
+            _ => {
+                msg!("Referrer {} isn't registered.", referrer);
+                Err(CustomError::UnregisteredReferrer.into())
+            }
+        }
+    }
+
+    fn check_pda(program_id: &Pubkey, state_pda: &AccountInfo, referrer: &Pubkey, stats_pda: &AccountInfo) -> Result<u8, ProgramError> {
+        let (stats_pub, bump) = Pubkey::find_program_address(
+            &[state_pda.key.as_ref(), REFERRER_SEED, referrer.as_ref()],
+            program_id,
+        );
+
+        if stats_pub != *stats_pda.key {
+            msg!("Referrer account {} & generated PDA {} mismatch.", stats_pda.key, stats_pub);
+            return Err(CustomError::WrongReferrerAccount.into());
+        }
+
+        Ok(bump)
+    }
+
+    pub fn if_initialized(stats_pda: &AccountInfo) -> bool {
+        if stats_pda.data_is_empty() {
+            return false;
+        }
+        stats_pda.data.borrow()[0] != 0
+    }
+
+    pub fn save_to(&self, stats_pda: &AccountInfo) -> ProgramResult {
+        self.serialize(stats_pda.data.borrow_mut().deref_mut())?;
+
+        Ok(())
+    }
+
+    pub fn load_from(stats_pda: &AccountInfo) -> Result<Self, ProgramError> {
+        let data = stats_pda.data.borrow();
+        let mut buf: &[u8] = data.deref();
+        let stats = ReferrerStats::deserialize(&mut buf)?;
+        Ok(stats)
+    }
+}
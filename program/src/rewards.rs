@@ -0,0 +1,139 @@
+use crate::error::CustomError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use std::io::Cursor;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+
+pub const REWARDS_SEED: &[u8] = b"rewards";
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct RewardEntry {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub weight: u32,
+    pub stock: u32, // how many times the entry can be won yet
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct RewardTable {
+    pub version: RewardTableVersion,
+    pub state: Pubkey,
+    pub bump: u8,
+    pub entries: Vec<RewardEntry>,
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum RewardTableVersion {
+    Version1 = 1,
+}
+
+impl RewardTable {
+    pub fn size_for(entries: usize) -> usize {
+        size_of::<RewardTable>() + entries * size_of::<RewardEntry>()
+    }
+
+    pub fn verify_and_load(program_id: &Pubkey, rewards_pda: &AccountInfo, state_pda: &AccountInfo) -> Result<RewardTable, ProgramError> {
+        if rewards_pda.owner != program_id || !Self::if_initialized(rewards_pda) {
+            msg!("Reward table isn't initialized.");
+            return Err(CustomError::WrongRewardTable.into());
+        }
+
+        let table = Self::load_from(rewards_pda)?;
+
+        let seed = [state_pda.key.as_ref(), REWARDS_SEED, &[table.bump]];
+        let rewards_pub = Pubkey::create_program_address(&seed, program_id)?;
+
+        if rewards_pub != *rewards_pda.key || table.state != *state_pda.key {
+            msg!("Reward table doesn't belong to the specified state.");
+            return Err(CustomError::WrongRewardTable.into());
+        }
+
+        Ok(table)
+    }
+
+    pub fn if_initialized(rewards_pda: &AccountInfo) -> bool {
+        if rewards_pda.data_is_empty() {
+            return false;
+        }
+        rewards_pda.data.borrow()[0] != 0
+    }
+
+    pub fn serialized_len(&self) -> Result<usize, ProgramError> {
+        let mut cursor = Cursor::new(Vec::with_capacity(Self::size_for(self.entries.len())));
+
+        self.serialize(&mut cursor)?;
+
+        Ok(cursor.position() as usize)
+    }
+
+    pub fn save_to(&self, rewards_pda: &AccountInfo) -> ProgramResult {
+        self.serialize(rewards_pda.data.borrow_mut().deref_mut())?;
+
+        Ok(())
+    }
+
+    pub fn load_from(rewards_pda: &AccountInfo) -> Result<Self, ProgramError> {
+        let data = rewards_pda.data.borrow();
+        let mut buf: &[u8] = data.deref();
+        let table = RewardTable::deserialize(&mut buf)?;
+        Ok(table)
+    }
+
+    /// Picks an entry which is still in stock, each entry has a chance proportional to its weight.
+    pub fn pick(&self, random: u64) -> Option<usize> {
+        let total_weight: u64 = self.entries.iter()
+            .filter(|entry| entry.stock > 0)
+            .map(|entry| entry.weight as u64)
+            .sum();
+
+        if total_weight == 0 {
+            return None;
+        }
+
+        let mut point = random % total_weight;
+        for (index, entry) in self.entries.iter().enumerate() {
+            if entry.stock == 0 {
+                continue;
+            }
+            if point < entry.weight as u64 {
+                return Some(index);
+            }
+            point -= entry.weight as u64;
+        }
+
+        None
+    }
+}
+
+#[test]
+fn test_pick() {
+    let entry = |weight: u32, stock: u32| RewardEntry {
+        mint: Pubkey::new_unique(),
+        amount: 1,
+        weight,
+        stock,
+    };
+
+    let mut table = RewardTable {
+        version: RewardTableVersion::Version1,
+        state: Pubkey::new_unique(),
+        bump: 255,
+        entries: vec!(entry(1, 10), entry(0, 10), entry(3, 10), entry(5, 0)),
+    };
+
+    assert_eq!(table.pick(0), Some(0));
+    assert_eq!(table.pick(1), Some(2));
+    assert_eq!(table.pick(3), Some(2));
+    assert_eq!(table.pick(4), Some(0));
+
+    table.entries[0].stock = 0;
+    table.entries[2].stock = 0;
+    assert_eq!(table.pick(0), None);
+}
@@ -0,0 +1,19 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Roles granted by the owner for all lootboxes of the authority, the owner has all of them implicitly.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct RoleGrant {
+    pub account: Pubkey,
+    pub roles: u8,
+}
+
+impl RoleGrant {
+    pub const OPERATOR: u8 = 1; // schedule, supply, prices, rewards and randomness
+    pub const TREASURER: u8 = 2; // AdminWithdraw
+    pub const PAUSER: u8 = 4; // can only stop sales
+
+    pub fn has(&self, role: u8) -> bool {
+        (self.roles & role) == role
+    }
+}
@@ -0,0 +1,79 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::clock::UnixTimestamp;
+use solana_program::pubkey::Pubkey;
+
+use crate::message::Action;
+
+/// Backend key which authorizes ObtainTicket and Withdraw.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub enum Signer {
+    // compressed public key, the signature is passed in the instruction params
+    Secp256k1([u8; 33]),
+    // the signature is verified by the native Ed25519 program earlier in the same transaction
+    Ed25519(Pubkey),
+    // Ethereum address, the signature is over the EIP-191 personal message of the 32 bytes message hash
+    Eip191([u8; 20]),
+    // Ethereum address, the signature is over the EIP-712 typed data
+    Eip712([u8; 20]),
+}
+
+/// One vote in the threshold, after a rotation the previous key keeps voting until the cutoff.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct SignerSlot {
+    pub current: Signer,
+    pub previous: Option<Signer>,
+    pub previous_valid_until: u32,
+}
+
+impl SignerSlot {
+    pub fn new(signer: Signer) -> Self {
+        SignerSlot {
+            current: signer,
+            previous: None,
+            previous_valid_until: 0,
+        }
+    }
+
+    /// Keys accepted at `now`, the current one goes first.
+    pub fn keys(&self, now: UnixTimestamp) -> impl Iterator<Item = &Signer> {
+        let previous = self.previous.as_ref().filter(|_| now <= self.previous_valid_until as UnixTimestamp);
+        std::iter::once(&self.current).chain(previous)
+    }
+
+    pub fn contains(&self, signer: &Signer) -> bool {
+        self.current == *signer || self.previous.as_ref() == Some(signer)
+    }
+}
+
+/// Signers authorizing one action, so a key of one service can't authorize actions of another one.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct SignerSet {
+    pub action: Action,
+    pub signers: Vec<SignerSlot>,
+    pub threshold: u8, // distinct slots required
+}
+
+impl SignerSet {
+    pub fn new(action: Action, signer: Signer) -> Self {
+        SignerSet {
+            action,
+            signers: vec![SignerSlot::new(signer)],
+            threshold: 1,
+        }
+    }
+}
+
+#[test]
+fn test_rotated_keys() {
+    let old = Signer::Ed25519(Pubkey::new_unique());
+    let new = Signer::Ed25519(Pubkey::new_unique());
+    let slot = SignerSlot {
+        current: new.clone(),
+        previous: Some(old.clone()),
+        previous_valid_until: 100,
+    };
+
+    assert_eq!(slot.keys(100).collect::<Vec<_>>(), vec![&new, &old]);
+    assert_eq!(slot.keys(101).collect::<Vec<_>>(), vec![&new]);
+    assert!(slot.contains(&old));
+}
@@ -0,0 +1,22 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::mem::size_of;
+
+use crate::state::Price;
+
+/// Kind of ticket sold by the same lootbox, e.g. standard and premium boxes of one season.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct Sku {
+    pub tier: u8, // recorded in tickets, 0 is reserved for tickets without SKU
+    pub prices: Vec<Price>, // used instead of the state prices
+    pub max_supply: u32, // within the state max_supply
+    pub total_supply: u32,
+}
+
+impl Sku {
+    pub const MAX_PRICES: usize = 4;
+    pub const MAX_SIZE: usize = size_of::<Sku>() + Self::MAX_PRICES * Price::MAX_SIZE;
+
+    pub fn tickets_left(&self) -> u32 {
+        self.max_supply.saturating_sub(self.total_supply)
+    }
+}
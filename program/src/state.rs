@@ -1,4 +1,12 @@
+use crate::access::Access;
+use crate::config::Config;
+use crate::drop_schedule::DropSchedule;
 use crate::error::CustomError;
+use crate::randomness::RandomnessConfig;
+use crate::message::Action;
+use crate::phase::{Eligibility, Phase};
+use crate::signer::{SignerSet, SignerSlot};
+use crate::sku::Sku;
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::account_info::AccountInfo;
 use solana_program::entrypoint::ProgramResult;
@@ -8,7 +16,7 @@ use solana_program::pubkey::Pubkey;
 use std::cmp::{min, PartialEq};
 use std::io::Cursor;
 use std::mem::size_of;
-use std::ops::{Deref, DerefMut};
+use std::ops::Deref;
 use solana_program::clock::{Clock, UnixTimestamp};
 
 pub const STATE_SEED: &[u8] = b"state";
@@ -30,13 +38,13 @@ pub struct StateV3 {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct Price {
+pub struct PriceV4 {
     pub amount: u64,
     pub ata: Pubkey,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct State {
+pub struct StateV4 {
     pub version: StateVersion,
     pub id: u16,
     pub owner: Pubkey,
@@ -47,9 +55,78 @@ pub struct State {
     pub end_ts: u32,
     pub name: String,
     pub signer: [u8; 33],
+    pub prices: Vec<PriceV4>,
+    pub base_url: String,
+    pub withdraw_counter: u32, // used for synchronization
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum PriceKind {
+    Token = 0, // SPL token transfer to the ATA
+    Native = 1, // lamports transfer to the account
+}
+
+#[derive(Clone, PartialEq, BorshSerialize, BorshDeserialize, Debug)]
+pub struct Price {
+    pub amount: u64,
+    pub ata: Pubkey, // token account or lamports recipient, depends on kind; gets all what's left after splits
+    pub kind: PriceKind,
+    pub splits: Vec<Split>,
+}
+
+/// Share of the payment (after the protocol fee) for a co-branding party.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct Split {
+    pub account: Pubkey, // same kind of account as the price one
+    pub bps: u16,
+}
+
+impl Price {
+    pub const MAX_SPLITS: usize = 5;
+    pub const MAX_SIZE: usize = size_of::<Price>() + Self::MAX_SPLITS * size_of::<Split>();
+    pub const MAX_BPS: u16 = 10_000;
+
+    /// Amounts for every split in the same order, rounded down; the rest goes to the price account.
+    pub fn split(&self, amount: u64) -> Vec<u64> {
+        self.splits.iter()
+            .map(|split| (amount as u128 * split.bps as u128 / Self::MAX_BPS as u128) as u64)
+            .collect()
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct State {
+    pub version: StateVersion,
+    pub id: u16,
+    pub owner: Pubkey, // admin who created the state, the lootboxes of the authority are owned through `Access`
+    pub vault_bump: u8,
+    pub total_supply: u32,
+    pub max_supply: u32,
+    pub begin_ts: u32,
+    pub end_ts: u32,
+    pub name: String,
+    pub signer_sets: Vec<SignerSet>, // at most one per action
     pub prices: Vec<Price>,
     pub base_url: String,
     pub withdraw_counter: u32, // used for synchronization
+    pub randomness: RandomnessConfig,
+    pub server_seed_hash: Option<[u8; 32]>, // committed before the sale
+    pub server_seed: Option<[u8; 32]>, // revealed after the sale
+    pub legacy_messages_until: u32, // signatures of messages without domain are accepted until then
+    pub authority: Pubkey, // the admin who created the state, the state and the vault seeds are derived from it
+    pub paused: u8, // PAUSE_* bits
+    pub referral_bps: u16, // share of the payment (after the protocol fee) for the referrer
+    pub referral_registration: bool, // referrers are registered only with the owner or operator approval
+    pub max_tickets_per_wallet: u32, // 0 is unlimited
+    pub window_limit: u32, // tickets per wallet during any window_duration, at most user::MAX_WINDOW_LIMIT
+    pub window_duration: u32, // seconds, 0 disables the window limit
+    pub allowlist_root: Option<[u8; 32]>, // only wallets with a proof can buy
+    pub phases: Vec<Phase>, // ordered and non-overlapping, the sale runs only during them if any
+    pub drop_schedule: Option<DropSchedule>, // purchases run only during drop windows if any
+    pub skus: Vec<Sku>, // a purchase must pick one of them if any
+    pub committed_tickets: u32, // committed and not opened or expired yet, the randomness can't be changed meanwhile
 }
 
 #[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
@@ -61,10 +138,26 @@ pub enum StateVersion {
     Version2 = 2,
     Version3 = 3,
     Version4 = 4,
+    Version5 = 5,
 }
 
 impl State {
-    pub const MAX_STATE_SIZE: usize = size_of::<State>() + 1024;
+    pub const MAX_SIGNERS: usize = 10; // in all sets
+    pub const MAX_SIGNER_SETS: usize = 2; // one per Action
+    pub const MAX_PRICES: usize = 8;
+    pub const MAX_PHASES: usize = 5;
+    pub const MAX_SKUS: usize = 4;
+    // every list at its cap, 1024 bytes are left for the name and the base url as before
+    pub const MAX_STATE_SIZE: usize = size_of::<State>() + 1024
+        + Self::MAX_SIGNER_SETS * size_of::<SignerSet>() + Self::MAX_SIGNERS * size_of::<SignerSlot>()
+        + Self::MAX_PRICES * Price::MAX_SIZE
+        + Self::MAX_PHASES * Phase::MAX_SIZE
+        + Self::MAX_SKUS * Sku::MAX_SIZE;
+
+    pub const PAUSE_BUY: u8 = 1;
+    pub const PAUSE_OBTAIN: u8 = 2;
+    pub const PAUSE_WITHDRAW: u8 = 4;
+    pub const PAUSE_OPEN: u8 = 8;
 
     pub fn verify_and_load(program_id: &Pubkey, state_pda: &AccountInfo, lootbox_id: u16, bump: Option<u8>) -> Result<State, ProgramError> {
         if !State::if_initialized(state_pda) {
@@ -80,10 +173,10 @@ impl State {
         let state = Self::load_from(state_pda)?;
 
         let state_pub = if bump.is_some() {
-            let seed = [state.owner.as_ref(), STATE_SEED, &lootbox_id.to_be_bytes(), &[bump.unwrap()]];
+            let seed = [state.authority.as_ref(), STATE_SEED, &lootbox_id.to_be_bytes(), &[bump.unwrap()]];
             Pubkey::create_program_address(&seed, program_id)?
         } else {
-            let seed = [state.owner.as_ref(), STATE_SEED, &lootbox_id.to_be_bytes()];
+            let seed = [state.authority.as_ref(), STATE_SEED, &lootbox_id.to_be_bytes()];
             Pubkey::find_program_address(&seed, program_id).0
         };
 
@@ -95,6 +188,20 @@ impl State {
         Ok(state)
     }
 
+    /// Loads the state for instructions which only the owner can call, the owner is kept in the access account of the authority.
+    pub fn verify_and_load_owned(program_id: &Pubkey, admin: &AccountInfo, state_pda: &AccountInfo, access_pda: &AccountInfo, lootbox_id: u16) -> Result<(State, Access), ProgramError> {
+        if !admin.is_signer {
+            return Err(CustomError::WrongSigner.into());
+        }
+
+        let state = Self::verify_and_load(program_id, state_pda, lootbox_id, None)?;
+        let access = Access::verify_and_load(program_id, &state.authority, access_pda)?;
+
+        access.check_owner(admin)?;
+
+        Ok((state, access))
+    }
+
     pub fn if_initialized(state_pda: &AccountInfo) -> bool {
         if state_pda.data_is_empty() {
             return false;
@@ -110,7 +217,7 @@ impl State {
     }
 
     pub fn get_last_version() -> u8 {
-        StateVersion::Version4 as u8
+        StateVersion::Version5 as u8
     }
 
     pub fn serialized_len(&self) -> Result<usize, ProgramError> {
@@ -122,8 +229,16 @@ impl State {
         Ok(cursor.position() as usize)
     }
 
+    /// The limits of the state lists together may take more than the account has,
+    /// so the state is checked to fit before it's written.
     pub fn save_to(&self, state_pda: &AccountInfo) -> ProgramResult {
-        self.serialize(state_pda.data.borrow_mut().deref_mut())?;
+        let data = borsh::to_vec(self)?;
+        if data.len() > state_pda.data_len() {
+            msg!("State takes {} bytes, but the account has {}.", data.len(), state_pda.data_len());
+            return Err(CustomError::NotEnoughSpace.into());
+        }
+
+        state_pda.data.borrow_mut()[..data.len()].copy_from_slice(&data);
 
         Ok(())
     }
@@ -135,27 +250,110 @@ impl State {
         Ok(state)
     }
 
-    pub fn find_price(&self, price_ata: &AccountInfo) -> Result<u64, ProgramError> {
-        for price in &self.prices {
-            if price.ata == *price_ata.key {
-                return Ok(price.amount);
-            }
+    /// None stands for tickets without SKU, e.g. obtained ones.
+    pub fn find_sku(&self, tier: Option<u8>) -> Result<Option<&Sku>, ProgramError> {
+        match tier {
+            Some(tier) => match self.skus.iter().find(|sku| sku.tier == tier) {
+                Some(sku) => Ok(Some(sku)),
+                None => {
+                    msg!("SKU {} doesn't exist.", tier);
+                    Err(CustomError::WrongSku.into())
+                }
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// The price of the SKU, or the state one with the amount of the active phase if the phase overrides it.
+    /// Phases override the state prices only, SKU prices are the same in every phase.
+    pub fn find_price(&self, price_ata: &AccountInfo, sku: Option<&Sku>, clock: &Clock) -> Result<Price, ProgramError> {
+        if let Some(sku) = sku {
+            return match sku.prices.iter().find(|price| price.ata == *price_ata.key) {
+                Some(price) => Ok(price.clone()),
+                None => Err(CustomError::WrongPaymentAta.into()),
+            };
+        }
+
+        let Some(price) = self.prices.iter().find(|price| price.ata == *price_ata.key) else {
+            return Err(CustomError::WrongPaymentAta.into());
+        };
+
+        let mut price = price.clone();
+        let price_override = self.active_phase(clock)
+            .and_then(|phase| phase.price_overrides.iter().find(|x| x.ata == price.ata));
+        if let Some(price_override) = price_override {
+            msg!("Phase price {} instead of {}.", price_override.amount, price.amount);
+            price.amount = price_override.amount;
         }
 
-        Err(CustomError::WrongPaymentAta.into())
+        Ok(price)
     }
 
-    pub fn check_and_get_correct_count(&self, count: u8) -> Result<u8, ProgramError> {
+    pub fn check_and_get_correct_count(&self, count: u8, sku: Option<&Sku>, clock: &Clock) -> Result<u8, ProgramError> {
         if self.total_supply >= self.max_supply {
             msg!("state.total_supply >= state.max_supply");
             return Err(CustomError::MaxSupplyReached.into());
         }
 
-        let tickets_left = self.max_supply - self.total_supply;
+        let mut tickets_left = self.max_supply - self.total_supply;
+
+        if let Some(phase_left) = self.active_phase(clock).and_then(Phase::tickets_left) {
+            if phase_left == 0 {
+                msg!("The phase supply is sold out.");
+                return Err(CustomError::MaxSupplyReached.into());
+            }
+            tickets_left = min(tickets_left, phase_left);
+        }
+
+        if let Some(sku) = sku {
+            if sku.tickets_left() == 0 {
+                msg!("SKU {} supply {} is sold out.", sku.tier, sku.max_supply);
+                return Err(CustomError::MaxSupplyReached.into());
+            }
+            tickets_left = min(tickets_left, sku.tickets_left());
+        }
 
         Ok(min(count as u32, tickets_left) as u8)
     }
 
+    /// Drop windows limit purchases only, tickets obtained with a signature don't depend on them.
+    pub fn check_drop_window(&self, count: u8, clock: &Clock) -> Result<u8, ProgramError> {
+        let Some(drop_schedule) = &self.drop_schedule else {
+            return Ok(count);
+        };
+
+        if drop_schedule.current_window(clock.unix_timestamp).is_none() {
+            msg!("too early, now {} is before the next drop window", clock.unix_timestamp);
+            return Err(CustomError::TooEarly.into());
+        }
+
+        let window_left = drop_schedule.tickets_left(clock.unix_timestamp);
+        if window_left == 0 {
+            msg!("The drop window supply {} is sold out.", drop_schedule.supply);
+            return Err(CustomError::MaxSupplyReached.into());
+        }
+
+        Ok(min(count as u32, window_left) as u8)
+    }
+
+    /// Counts purchased tickets in the drop window.
+    pub fn add_dropped(&mut self, count: u32, clock: &Clock) {
+        if let Some(drop_schedule) = &mut self.drop_schedule {
+            drop_schedule.add_issued(count, clock.unix_timestamp);
+        }
+    }
+
+    /// Counts issued tickets in the total supply, the supply of the SKU and the active phase.
+    pub fn add_issued(&mut self, count: u32, tier: Option<u8>, clock: &Clock) {
+        self.total_supply += count;
+        if let Some(sku) = self.skus.iter_mut().find(|sku| Some(sku.tier) == tier) {
+            sku.total_supply += count;
+        }
+        if let Some(phase) = self.active_phase_mut(clock) {
+            phase.issued += count;
+        }
+    }
+
     pub fn check_time(&self, clock: &Clock) -> ProgramResult {
         if (self.begin_ts as UnixTimestamp) > clock.unix_timestamp {
             msg!("too early, now {} is less then begin {}", clock.unix_timestamp, self.begin_ts);
@@ -165,6 +363,76 @@ impl State {
             msg!("too late, now {} is grater then end {}", clock.unix_timestamp, self.end_ts);
             return Err(CustomError::TooLate.into());
         }
+        if self.server_seed.is_some() {
+            msg!("too late, the server seed is already revealed");
+            return Err(CustomError::TooLate.into());
+        }
+        if !self.phases.is_empty() && self.active_phase(clock).is_none() {
+            // between two phases it's too early for the next one
+            if self.phases.iter().any(|phase| (phase.begin_ts as UnixTimestamp) > clock.unix_timestamp) {
+                msg!("too early, now {} is before the next phase", clock.unix_timestamp);
+                return Err(CustomError::TooEarly.into());
+            }
+            msg!("too late, now {} is after the last phase", clock.unix_timestamp);
+            return Err(CustomError::TooLate.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn active_phase(&self, clock: &Clock) -> Option<&Phase> {
+        self.phases.iter().find(|phase| phase.is_active(clock.unix_timestamp))
+    }
+
+    pub fn active_phase_mut(&mut self, clock: &Clock) -> Option<&mut Phase> {
+        self.phases.iter_mut().find(|phase| phase.is_active(clock.unix_timestamp))
+    }
+
+    /// The allowlist of the active phase replaces the state one.
+    pub fn allowlist_root(&self, clock: &Clock) -> Option<[u8; 32]> {
+        match self.active_phase(clock).map(|phase| &phase.eligibility) {
+            Some(Eligibility::Allowlist { root }) => Some(*root),
+            _ => self.allowlist_root,
+        }
+    }
+
+    /// Wallets get their accounts only if there is something to count in them.
+    pub fn has_wallet_limits(&self) -> bool {
+        self.max_tickets_per_wallet != 0 || self.window_duration != 0
+    }
+
+    pub fn check_not_paused(&self, config: &Config, action: u8) -> ProgramResult {
+        if config.paused {
+            msg!("The program is paused.");
+            return Err(CustomError::Paused.into());
+        }
+        if (self.paused & action) != 0 {
+            msg!("The action {} is paused, paused bits are {}.", action, self.paused);
+            return Err(CustomError::Paused.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn signer_set(&self, action: Action) -> Option<&SignerSet> {
+        self.signer_sets.iter().find(|set| set.action == action)
+    }
+
+    pub fn signer_set_mut(&mut self, action: Action) -> Option<&mut SignerSet> {
+        self.signer_sets.iter_mut().find(|set| set.action == action)
+    }
+
+    pub fn signers_count(&self) -> usize {
+        self.signer_sets.iter().map(|set| set.signers.len()).sum()
+    }
+
+    pub fn check_thresholds(&self) -> ProgramResult {
+        for set in &self.signer_sets {
+            if set.threshold == 0 || set.threshold as usize > set.signers.len() {
+                msg!("{:?} threshold {} must be between 1 and {} signers.", set.action, set.threshold, set.signers.len());
+                return Err(CustomError::WrongThreshold.into());
+            }
+        }
 
         Ok(())
     }
@@ -183,21 +451,14 @@ impl State {
         Ok(())
     }
     pub fn check_vault(&self, program_id: &Pubkey, vault_pda: &AccountInfo) -> ProgramResult {
-        self.check_vault_with_seed(program_id, vault_pda, &[&self.owner.to_bytes(), VAULT, &[self.vault_bump]])
+        self.check_vault_with_seed(program_id, vault_pda, &[&self.authority.to_bytes(), VAULT, &[self.vault_bump]])
     }
 }
 
-#[test]
-fn test_save_to() {
-    let owner = Pubkey::new_unique();
-    println!("Owner: {:?}", owner.to_bytes());
-    let signer = [0; 33];
-    println!("Signer: {:?}", signer);
-    let payment_ata = Pubkey::new_unique();
-    println!("Payment: {:?}", payment_ata.to_bytes());
-
-    let state = State {
-        version: StateVersion::Version4,
+#[cfg(test)]
+pub fn sample_state(owner: Pubkey, signer: crate::signer::Signer, payment_ata: Pubkey) -> State {
+    State {
+        version: StateVersion::Version5,
         id: 42,
         owner,
         total_supply: 0,
@@ -205,16 +466,160 @@ fn test_save_to() {
         begin_ts: 1,
         end_ts: 2,
         name: "DLS 1".to_string(),
-        signer,
+        signer_sets: vec![SignerSet::new(Action::Obtain, signer.clone()), SignerSet::new(Action::Withdraw, signer)],
         vault_bump: 255,
-        prices: vec!(Price { amount: 123, ata: Pubkey::new_unique() }),
+        prices: vec!(
+            Price { amount: 123, ata: payment_ata, kind: PriceKind::Token, splits: vec![] },
+            Price {
+                amount: 1_000_000,
+                ata: Pubkey::new_unique(),
+                kind: PriceKind::Native,
+                splits: vec![Split { account: Pubkey::new_unique(), bps: 2500 }],
+            },
+        ),
         base_url: "https://example.com/".to_string(),
         withdraw_counter: 0,
-    };
+        randomness: RandomnessConfig::SlotHashes { delay: 2 },
+        server_seed_hash: None,
+        server_seed: None,
+        legacy_messages_until: 0,
+        authority: owner,
+        paused: 0,
+        referral_bps: 0,
+        referral_registration: false,
+        max_tickets_per_wallet: 0,
+        window_limit: 0,
+        window_duration: 0,
+        allowlist_root: None,
+        phases: vec![Phase {
+            begin_ts: 1,
+            end_ts: 2,
+            price_overrides: vec![crate::phase::PriceOverride { ata: payment_ata, amount: 100 }],
+            max_supply: 10,
+            issued: 0,
+            eligibility: Eligibility::Allowlist { root: [1; 32] },
+        }],
+        drop_schedule: Some(DropSchedule::new(1, 86_400, 3600, 1000)),
+        skus: vec![Sku {
+            tier: 1,
+            prices: vec![Price { amount: 5, ata: payment_ata, kind: PriceKind::Token, splits: vec![] }],
+            max_supply: 10,
+            total_supply: 0,
+        }],
+        committed_tickets: 0,
+    }
+}
+
+#[test]
+fn test_save_to() {
+    let owner = Pubkey::new_unique();
+    println!("Owner: {:?}", owner.to_bytes());
+    let signer = crate::signer::Signer::Secp256k1([0; 33]);
+    println!("Signer: {:?}", signer);
+    let payment_ata = Pubkey::new_unique();
+    println!("Payment: {:?}", payment_ata.to_bytes());
+
+    let state = sample_state(owner, signer, payment_ata);
 
     let mut buf: Vec<u8> = Vec::with_capacity(State::MAX_STATE_SIZE);
 
     state.serialize(&mut buf).unwrap();
 
     println!("Result: {:?}", buf);
-}
\ No newline at end of file
+}
+#[test]
+fn test_price_split() {
+    let price = Price {
+        amount: 1,
+        ata: Pubkey::new_unique(),
+        kind: PriceKind::Token,
+        splits: vec![
+            Split { account: Pubkey::new_unique(), bps: 3333 },
+            Split { account: Pubkey::new_unique(), bps: 6667 },
+        ],
+    };
+
+    assert_eq!(price.split(100), vec![33, 66]);
+    assert_eq!(price.split(u64::MAX), vec![6148299799767393553, 12298444273942158061]);
+}
+
+#[test]
+fn test_phase_price() {
+    let payment_ata = Pubkey::new_unique();
+    let state = sample_state(Pubkey::new_unique(), crate::signer::Signer::Secp256k1([0; 33]), payment_ata);
+    let clock = Clock { unix_timestamp: 1, ..Clock::default() };
+
+    let mut lamports = 0;
+    let mut data = vec![];
+    let owner = spl_token::id();
+    let price_ata = AccountInfo::new(&payment_ata, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+    // the discount of the phase applies to the state price
+    assert_eq!(state.find_price(&price_ata, None, &clock).unwrap().amount, 100);
+    // a SKU bought during the discounted phase keeps its own price
+    let sku = state.find_sku(Some(1)).unwrap();
+    assert_eq!(state.find_price(&price_ata, sku, &clock).unwrap().amount, 5);
+    // after the phase the state price is back
+    let clock = Clock { unix_timestamp: 3, ..Clock::default() };
+    assert_eq!(state.find_price(&price_ata, None, &clock).unwrap().amount, 123);
+}
+
+#[test]
+fn test_save_to_not_enough_space() {
+    let state = sample_state(Pubkey::new_unique(), crate::signer::Signer::Secp256k1([0; 33]), Pubkey::new_unique());
+    let size = borsh::to_vec(&state).unwrap().len();
+
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = vec![0; size - 1];
+    let state_pda = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false, 0);
+    assert_eq!(state.save_to(&state_pda), Err(CustomError::NotEnoughSpace.into()));
+
+    let mut data = vec![0; size];
+    let state_pda = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false, 0);
+    state.save_to(&state_pda).unwrap();
+    assert_eq!(State::load_from(&state_pda).unwrap().skus, state.skus);
+}
+
+#[test]
+fn test_max_state_size() {
+    use crate::phase::PriceOverride;
+    use crate::randomness::VrfConfig;
+    use crate::signer::Signer;
+    use solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+
+    let price = Price {
+        amount: u64::MAX,
+        ata: Pubkey::new_unique(),
+        kind: PriceKind::Token,
+        splits: vec![Split { account: Pubkey::new_unique(), bps: 100 }; Price::MAX_SPLITS],
+    };
+    let slot = SignerSlot {
+        current: Signer::Secp256k1([2; 33]),
+        previous: Some(Signer::Secp256k1([3; 33])),
+        previous_valid_until: 1,
+    };
+    let mut state = sample_state(Pubkey::new_unique(), Signer::Secp256k1([2; 33]), Pubkey::new_unique());
+    state.name = "n".repeat(256);
+    state.base_url = "u".repeat(768);
+    state.signer_sets[0].signers = vec![slot.clone(); State::MAX_SIGNERS / 2];
+    state.signer_sets[1].signers = vec![slot; State::MAX_SIGNERS / 2];
+    state.prices = vec![price.clone(); State::MAX_PRICES];
+    state.randomness = RandomnessConfig::Vrf(VrfConfig { oracle_program: Pubkey::new_unique(), seed_offset: 0, slot_offset: 32, result_offset: 40 });
+    state.server_seed_hash = Some([1; 32]);
+    state.server_seed = Some([2; 32]);
+    state.allowlist_root = Some([3; 32]);
+    state.phases = vec![Phase {
+        begin_ts: 1,
+        end_ts: 2,
+        price_overrides: vec![PriceOverride { ata: Pubkey::new_unique(), amount: 1 }; Phase::MAX_PRICE_OVERRIDES],
+        max_supply: 10,
+        issued: 0,
+        eligibility: Eligibility::Allowlist { root: [1; 32] },
+    }; State::MAX_PHASES];
+    state.skus = vec![Sku { tier: 1, prices: vec![price; Sku::MAX_PRICES], max_supply: 10, total_supply: 0 }; State::MAX_SKUS];
+
+    assert!(borsh::to_vec(&state).unwrap().len() <= State::MAX_STATE_SIZE);
+    // the account is created and extended by the program
+    const { assert!(State::MAX_STATE_SIZE <= MAX_PERMITTED_DATA_INCREASE) };
+}
@@ -13,6 +13,28 @@ use std::mem::size_of;
 use std::ops::{Deref, DerefMut};
 
 pub const TICKET_PREFIX: &[u8; 4] = b"AGLB";
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct TicketV0 {
+    pub prefix: [u8; 4],
+    pub version: u8,
+    pub owner: Pubkey,
+    pub lootbox_id: u16,
+    pub issue_index: u32,
+    pub external_id: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct TicketV1 {
+    pub prefix: [u8; 4],
+    pub version: u8,
+    pub owner: Pubkey,
+    pub lootbox_id: u16,
+    pub issue_index: u32,
+    pub external_id: u32,
+    pub state: Pubkey,
+    pub commit_slot: u64,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct Ticket {
     pub prefix: [u8; 4],
@@ -21,16 +43,23 @@ pub struct Ticket {
     pub lootbox_id: u16,
     pub issue_index: u32,
     pub external_id: u32,
+    pub state: Pubkey, // Pubkey::default() for tickets issued before Version1
+    pub commit_slot: u64, // 0 until the owner commits to open the ticket
+    pub tier: u8, // SKU tier, 0 for tickets issued without SKU
+    pub randomness_account: Pubkey, // VRF request bound at the commit, Pubkey::default() for SlotHashes
 }
 
 pub enum TicketVersion {
     Version0 = 0,
+    Version1 = 1,
+    Version2 = 2,
 }
 
 impl Ticket {
     pub fn verify_and_create<'a>(program_id: &Pubkey,
                                  system_program: &AccountInfo<'a>,
                                  buyer: &AccountInfo<'a>,
+                                 state: &Pubkey,
                                  lootbox_id: u16,
                                  ticket_seed: u32,
                                  buy_index: u8,
@@ -38,6 +67,7 @@ impl Ticket {
                                  ticket_pda: &AccountInfo<'a>,
                                  bump: Option<u8>,
                                  external_id: Option<u32>,
+                                 tier: u8,
     ) -> Result<Ticket, ProgramError> {
         if !buyer.is_signer {
             msg!("Buyer must sign the transaction.");
@@ -70,6 +100,10 @@ impl Ticket {
             lootbox_id,
             issue_index,
             external_id: external_id.unwrap_or(0),
+            state: *state,
+            commit_slot: 0,
+            tier,
+            randomness_account: Pubkey::default(),
         };
 
         let space = Ticket::serialized_len(&ticket)?;
@@ -95,7 +129,7 @@ impl Ticket {
     pub fn verify_and_close<'a>(
         owner: &AccountInfo<'a>,
         ticket_pda: &AccountInfo<'a>,
-    ) -> ProgramResult {
+    ) -> Result<Ticket, ProgramError> {
         if !owner.is_signer {
             msg!("Owner must sign the transaction.");
             return Err(CustomError::WrongSigner.into());
@@ -112,6 +146,16 @@ impl Ticket {
             return Err(CustomError::WrongTicketOwner.into());
         }
 
+        Self::close(owner, ticket_pda)?;
+
+        Ok(ticket)
+    }
+
+    /// Returns the rent to the owner and wipes the ticket data.
+    pub fn close<'a>(
+        owner: &AccountInfo<'a>,
+        ticket_pda: &AccountInfo<'a>,
+    ) -> ProgramResult {
         // Безопасно переносим lamports обратно
         **owner.lamports.borrow_mut() += **ticket_pda.lamports.borrow();
         **ticket_pda.lamports.borrow_mut() = 0;
@@ -136,12 +180,12 @@ impl Ticket {
     }
 
     pub fn get_version(ticket_pda: &AccountInfo) -> u8 {
-        let version = ticket_pda.data.borrow()[5];
+        let version = ticket_pda.data.borrow()[TICKET_PREFIX.len()];
         version
     }
 
     pub fn get_last_version() -> u8 {
-        TicketVersion::Version0 as u8
+        TicketVersion::Version2 as u8
     }
 
     pub fn serialized_len(&self) -> Result<usize, ProgramError> {
@@ -160,6 +204,44 @@ impl Ticket {
     }
 
     pub fn load_from(ticket_pda: &AccountInfo) -> Result<Self, ProgramError> {
+        if Self::get_version(ticket_pda) == TicketVersion::Version0 as u8 {
+            let data = ticket_pda.data.borrow();
+            let mut buf: &[u8] = data.deref();
+            let old_ticket = TicketV0::deserialize(&mut buf)?;
+
+            return Ok(Ticket {
+                prefix: old_ticket.prefix,
+                version: old_ticket.version,
+                owner: old_ticket.owner,
+                lootbox_id: old_ticket.lootbox_id,
+                issue_index: old_ticket.issue_index,
+                external_id: old_ticket.external_id,
+                state: Pubkey::default(),
+                commit_slot: 0,
+                tier: 0,
+                randomness_account: Pubkey::default(),
+            });
+        }
+
+        if Self::get_version(ticket_pda) == TicketVersion::Version1 as u8 {
+            let data = ticket_pda.data.borrow();
+            let mut buf: &[u8] = data.deref();
+            let old_ticket = TicketV1::deserialize(&mut buf)?;
+
+            return Ok(Ticket {
+                prefix: old_ticket.prefix,
+                version: old_ticket.version,
+                owner: old_ticket.owner,
+                lootbox_id: old_ticket.lootbox_id,
+                issue_index: old_ticket.issue_index,
+                external_id: old_ticket.external_id,
+                state: old_ticket.state,
+                commit_slot: old_ticket.commit_slot,
+                tier: 0,
+                randomness_account: Pubkey::default(),
+            });
+        }
+
         let data = ticket_pda.data.borrow();
         let mut buf: &[u8] = data.deref();
         let ticket = Ticket::deserialize(&mut buf)?;
@@ -0,0 +1,141 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::Clock;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program::invoke_signed;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::Sysvar;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::instruction::transfer_checked;
+use spl_token_2022::state::Mint;
+
+pub fn check_token_program(token_program: &AccountInfo) -> ProgramResult {
+    if !spl_token::check_id(token_program.key) && !spl_token_2022::check_id(token_program.key) {
+        msg!("Wrong SPL token program id {}", token_program.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    Ok(())
+}
+
+/// Picks the token program which owns the specified mint.
+pub fn token_program_for<'a, 'b>(mint: &AccountInfo<'a>,
+                                 token_programs: &[&'b AccountInfo<'a>],
+) -> Result<&'b AccountInfo<'a>, ProgramError> {
+    for token_program in token_programs {
+        if mint.owner == token_program.key {
+            check_token_program(token_program)?;
+            return Ok(token_program);
+        }
+    }
+
+    msg!("Token program for mint {} wasn't specified.", mint.key);
+    Err(ProgramError::IncorrectProgramId)
+}
+
+/// The wallet receives lamports directly, and tokens (`mint`, `token_program`) to its associated token account.
+pub fn recipient_for(wallet: &Pubkey, mint: Option<(&AccountInfo, &AccountInfo)>) -> Pubkey {
+    match mint {
+        Some((mint, token_program)) => get_associated_token_address_with_program_id(wallet, mint.key, token_program.key),
+        None => *wallet,
+    }
+}
+
+/// Returns the amount which has to be sent, so that the receiver gets exactly `amount`.
+/// Only Token-2022 mints with the transfer fee extension charge something on top.
+pub fn amount_with_fee(mint: &AccountInfo, amount: u64) -> Result<u64, ProgramError> {
+    if !spl_token_2022::check_id(mint.owner) {
+        return Ok(amount);
+    }
+
+    let data = mint.data.borrow();
+    let mint_state = StateWithExtensions::<Mint>::unpack(&data)?;
+    let fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(fee_config) => fee_config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+        Err(_) => 0,
+    };
+
+    if fee > 0 {
+        msg!("Transfer fee {} is added to {}", fee, amount);
+    }
+
+    amount.checked_add(fee).ok_or(ProgramError::ArithmeticOverflow)
+}
+
+pub fn transfer<'a>(token_program: &AccountInfo<'a>,
+                    source: &AccountInfo<'a>,
+                    mint: &AccountInfo<'a>,
+                    destination: &AccountInfo<'a>,
+                    authority: &AccountInfo<'a>,
+                    amount: u64,
+                    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    check_token_program(token_program)?;
+
+    if mint.owner != token_program.key {
+        msg!("Mint {} isn't owned by token program {}", mint.key, token_program.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let decimals = {
+        let data = mint.data.borrow();
+        StateWithExtensions::<Mint>::unpack(&data)?.base.decimals
+    };
+
+    invoke_signed(
+        &transfer_checked(
+            token_program.key,
+            source.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            source.clone(),
+            mint.clone(),
+            destination.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+/// Creates the owner's associated token account, if it doesn't exist yet; the owner pays for it.
+pub fn create_ata_if_empty<'a>(owner: &AccountInfo<'a>,
+                               ata: &AccountInfo<'a>,
+                               mint: &AccountInfo<'a>,
+                               system_program: &AccountInfo<'a>,
+                               token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    if !ata.data_is_empty() {
+        return Ok(());
+    }
+
+    invoke_signed(
+        &create_associated_token_account(
+            owner.key,
+            owner.key,
+            mint.key,
+            token_program.key,
+        ),
+        &[
+            owner.clone(),
+            ata.clone(),
+            owner.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+        ],
+        &[],
+    )
+}
@@ -0,0 +1,252 @@
+use crate::error::CustomError;
+use crate::state::State;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::program::invoke_signed;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::create_account;
+use solana_program::sysvar::rent::Rent;
+use solana_program::sysvar::Sysvar;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+
+pub const USER_SEED: &[u8] = b"user";
+pub const MAX_WINDOW_LIMIT: usize = 32;
+
+/// Per (state, wallet) account, it's created on demand and paid by the wallet.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct UserState {
+    pub version: UserStateVersion,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub bump: u8,
+    pub withdraw_nonce: u64, // each withdraw signature is bound to the current value
+    pub tickets: u32, // bought and obtained during the lifetime
+    pub recent_tickets: [u32; MAX_WINDOW_LIMIT], // issue time of the latest tickets within the window, 0 is empty
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum UserStateVersion {
+    Version1 = 1,
+}
+
+impl UserState {
+    pub const SIZE: usize = size_of::<UserState>();
+
+    pub fn load_or_create<'a>(program_id: &Pubkey,
+                              state_pda: &AccountInfo<'a>,
+                              owner: &AccountInfo<'a>,
+                              user_pda: &AccountInfo<'a>,
+                              system_program: &AccountInfo<'a>,
+    ) -> Result<UserState, ProgramError> {
+        let (user_pub, bump) = Pubkey::find_program_address(
+            &[state_pda.key.as_ref(), USER_SEED, owner.key.as_ref()],
+            program_id,
+        );
+
+        if user_pub != *user_pda.key {
+            msg!("User account {} & generated PDA {} mismatch.", user_pda.key, user_pub);
+            return Err(CustomError::WrongUserAccount.into());
+        }
+
+        if Self::if_initialized(user_pda) {
+            return Self::load_from(user_pda);
+        }
+
+        if !owner.is_signer {
+            msg!("Owner must sign the transaction.");
+            return Err(CustomError::WrongSigner.into());
+        }
+
+        msg!("Create user account {}.", user_pub);
+        let lamports = Rent::get()?.minimum_balance(Self::SIZE);
+
+        invoke_signed(
+            &create_account(
+                owner.key,
+                user_pda.key,
+                lamports,
+                Self::SIZE as u64,
+                program_id,
+            ),
+            &[owner.clone(), user_pda.clone(), system_program.clone()],
+            &[&[state_pda.key.as_ref(), USER_SEED, owner.key.as_ref(), &[bump]]],
+        )?;
+
+        Ok(UserState {
+            version: UserStateVersion::Version1,
+            state: *state_pda.key,
+            owner: *owner.key,
+            bump,
+            withdraw_nonce: 0,
+            tickets: 0,
+            recent_tickets: [0; MAX_WINDOW_LIMIT],
+        })
+    }
+
+    /// Counts new tickets against the wallet limits of the state and the allowlist allocation of the wallet, if any.
+    /// The window is rolling: tickets issued during the last `window_duration` seconds before now are counted.
+    pub fn add_tickets(&mut self, state: &State, count: u32, allocation: Option<u32>, now: u32) -> ProgramResult {
+        let tickets = self.tickets.saturating_add(count);
+        if let Some(allocation) = allocation {
+            if tickets > allocation {
+                msg!("Wallet would have {} tickets, but its allocation is {}.", tickets, allocation);
+                return Err(CustomError::WalletLimitReached.into());
+            }
+        }
+        if state.max_tickets_per_wallet != 0 && tickets > state.max_tickets_per_wallet {
+            msg!("Wallet has {} tickets, at most {} are allowed.", self.tickets, state.max_tickets_per_wallet);
+            return Err(CustomError::WalletLimitReached.into());
+        }
+
+        if state.window_duration != 0 {
+            let window_start = now.saturating_sub(state.window_duration);
+            let mut recent: Vec<u32> = self.recent_tickets.iter()
+                .copied()
+                .filter(|issued_at| *issued_at != 0 && *issued_at > window_start)
+                .collect();
+
+            if recent.len() as u32 + count > state.window_limit {
+                msg!("Wallet has {} tickets since {}, at most {} are allowed per {} seconds.",
+                    recent.len(), window_start, state.window_limit, state.window_duration);
+                return Err(CustomError::WalletLimitReached.into());
+            }
+
+            // the limit is at most MAX_WINDOW_LIMIT, so all of them fit
+            recent.resize(recent.len() + count as usize, now);
+            recent.resize(MAX_WINDOW_LIMIT, 0);
+            self.recent_tickets.copy_from_slice(&recent);
+        }
+
+        self.tickets = tickets;
+
+        Ok(())
+    }
+
+    /// Every withdraw signature is bound to the current nonce, so it can be used only once.
+    pub fn use_withdraw_nonce(&mut self, nonce: u64) -> ProgramResult {
+        if nonce < self.withdraw_nonce {
+            msg!("Nonce {} has been already used, the current one is {}.", nonce, self.withdraw_nonce);
+            return Err(CustomError::SignatureAlreadyUsed.into());
+        }
+        if nonce != self.withdraw_nonce {
+            msg!("Nonce {} doesn't match with the current one {}.", nonce, self.withdraw_nonce);
+            return Err(CustomError::WrongNonce.into());
+        }
+
+        self.withdraw_nonce += 1;
+
+        Ok(())
+    }
+
+    pub fn if_initialized(user_pda: &AccountInfo) -> bool {
+        if user_pda.data_is_empty() {
+            return false;
+        }
+        user_pda.data.borrow()[0] != 0
+    }
+
+    pub fn save_to(&self, user_pda: &AccountInfo) -> ProgramResult {
+        self.serialize(user_pda.data.borrow_mut().deref_mut())?;
+
+        Ok(())
+    }
+
+    pub fn load_from(user_pda: &AccountInfo) -> Result<Self, ProgramError> {
+        let data = user_pda.data.borrow();
+        let mut buf: &[u8] = data.deref();
+        let user = UserState::deserialize(&mut buf)?;
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+fn sample_user() -> UserState {
+    UserState {
+        version: UserStateVersion::Version1,
+        state: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        bump: 255,
+        withdraw_nonce: 0,
+        tickets: 0,
+        recent_tickets: [0; MAX_WINDOW_LIMIT],
+    }
+}
+
+#[test]
+fn test_rolling_window() {
+    let mut state = crate::state::sample_state(Pubkey::new_unique(), crate::signer::Signer::Secp256k1([0; 33]), Pubkey::new_unique());
+    state.window_limit = 3;
+    state.window_duration = 100;
+    let mut user = sample_user();
+
+    user.add_tickets(&state, 2, None, 1000).unwrap();
+    user.add_tickets(&state, 1, None, 1050).unwrap();
+    assert_eq!(user.add_tickets(&state, 1, None, 1099), Err(CustomError::WalletLimitReached.into()));
+
+    // the first two leave the window, the one from 1050 is still in it
+    user.add_tickets(&state, 2, None, 1100).unwrap();
+    assert_eq!(user.add_tickets(&state, 1, None, 1149), Err(CustomError::WalletLimitReached.into()));
+    user.add_tickets(&state, 1, None, 1150).unwrap();
+    assert_eq!(user.tickets, 6);
+
+    // a failed purchase doesn't change the account
+    assert_eq!(user.add_tickets(&state, 4, None, 5000), Err(CustomError::WalletLimitReached.into()));
+    assert_eq!(user.tickets, 6);
+    assert_eq!(user.recent_tickets.iter().filter(|issued_at| **issued_at != 0).count(), 3);
+}
+
+#[test]
+fn test_lifetime_limit() {
+    let mut state = crate::state::sample_state(Pubkey::new_unique(), crate::signer::Signer::Secp256k1([0; 33]), Pubkey::new_unique());
+    state.max_tickets_per_wallet = 5;
+    let mut user = sample_user();
+
+    user.add_tickets(&state, 4, None, 1000).unwrap();
+    assert_eq!(user.add_tickets(&state, 2, None, 1_000_000), Err(CustomError::WalletLimitReached.into()));
+    user.add_tickets(&state, 1, None, 1_000_000).unwrap();
+    assert_eq!(user.add_tickets(&state, 1, None, 2_000_000), Err(CustomError::WalletLimitReached.into()));
+    assert_eq!(user.tickets, 5);
+    // without the window the issue times aren't recorded
+    assert_eq!(user.recent_tickets, [0; MAX_WINDOW_LIMIT]);
+}
+
+#[test]
+fn test_allocation() {
+    let mut state = crate::state::sample_state(Pubkey::new_unique(), crate::signer::Signer::Secp256k1([0; 33]), Pubkey::new_unique());
+    state.max_tickets_per_wallet = 10;
+    state.window_limit = 2;
+    state.window_duration = 100;
+    let mut user = sample_user();
+
+    // the allocation is lower than the lifetime limit
+    user.add_tickets(&state, 2, Some(3), 1000).unwrap();
+    assert_eq!(user.add_tickets(&state, 2, Some(3), 2000), Err(CustomError::WalletLimitReached.into()));
+    user.add_tickets(&state, 1, Some(3), 2000).unwrap();
+    assert_eq!(user.add_tickets(&state, 1, Some(3), 3000), Err(CustomError::WalletLimitReached.into()));
+
+    // the window still applies within a bigger allocation, tickets obtained without it count too
+    user.add_tickets(&state, 1, None, 3000).unwrap();
+    assert_eq!(user.add_tickets(&state, 2, Some(20), 3000), Err(CustomError::WalletLimitReached.into()));
+    user.add_tickets(&state, 1, Some(20), 3000).unwrap();
+    assert_eq!(user.tickets, 5);
+}
+
+#[test]
+fn test_withdraw_nonce() {
+    let mut user = sample_user();
+
+    assert_eq!(user.use_withdraw_nonce(1), Err(CustomError::WrongNonce.into()));
+    user.use_withdraw_nonce(0).unwrap();
+    // the same signed withdraw can't be replayed
+    assert_eq!(user.use_withdraw_nonce(0), Err(CustomError::SignatureAlreadyUsed.into()));
+    user.use_withdraw_nonce(1).unwrap();
+    assert_eq!(user.use_withdraw_nonce(0), Err(CustomError::SignatureAlreadyUsed.into()));
+    assert_eq!(user.use_withdraw_nonce(1), Err(CustomError::SignatureAlreadyUsed.into()));
+    assert_eq!(user.withdraw_nonce, 2);
+}
@@ -1,38 +1,307 @@
-use solana_program::clock::Clock;
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::{Clock, UnixTimestamp};
+use solana_program::ed25519_program;
 use solana_program::entrypoint::ProgramResult;
 use solana_program::hash::Hash;
+use solana_program::keccak;
 use solana_program::msg;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
 use solana_program::secp256k1_recover::secp256k1_recover;
+use solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
 use solana_program::sysvar::Sysvar;
 use crate::error::CustomError;
 use crate::instruction::Signature;
+use crate::message::Message;
+use crate::signer::Signer;
 use crate::state::State;
 
-pub fn verify_signature<'a>(message_hash: &Hash,
-                        expire_at: u32,
-                        signature: &Signature,
+// (secp256k1 curve order - 1) / 2, big endian
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+const ED25519_HEADER: usize = 2;
+const ED25519_OFFSETS: usize = 14;
+const ED25519_CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+// Ethereum wallets produce v = 27 + recovery id
+const ETHEREUM_RECOVERY_ID_BASE: u8 = 27;
+
+/// Counts distinct signers of the action set who signed the message, there must be at least the set threshold of them.
+/// Ed25519 signers are looked up in `instructions_sysvar`, the others are recovered from `signatures`.
+pub fn verify_signature(message: &Message,
+                        signatures: &[Signature],
                         state: &State,
+                        instructions_sysvar: &AccountInfo,
 ) -> ProgramResult {
     let clock = Clock::get()?;
-    if clock.unix_timestamp > expire_at as i64 {
+    if clock.unix_timestamp > message.expire_at as i64 {
         msg!("Signature expired {}", clock.unix_timestamp);
         return Err(CustomError::SignatureExpired.into());
     }
 
-    let recovered = secp256k1_recover(&message_hash.to_bytes(), signature.rec_id, &signature.rs).map_err(|e| {
-        msg!("Recovery error: {}", e);
-        CustomError::SignatureVerificationFailed
-    })?;
+    // (r, s) and (r, n - s) are both valid, only the lower one is accepted
+    if signatures.iter().any(|signature| signature.rs[32..64] > SECP256K1_HALF_ORDER[..]) {
+        msg!("Signature has high S value.");
+        return Err(CustomError::MalleableSignature.into());
+    }
+
+    check_approvals(message, signatures, state, clock.unix_timestamp, instructions_sysvar)
+}
+
+/// A slot approves once, so a signature repeated or made by both keys of a rotated slot counts once.
+fn check_approvals(message: &Message,
+                   signatures: &[Signature],
+                   state: &State,
+                   now: UnixTimestamp,
+                   instructions_sysvar: &AccountInfo,
+) -> ProgramResult {
+    let Some(set) = state.signer_set(message.action()) else {
+        msg!("There are no {:?} signers.", message.action());
+        return Err(CustomError::NotEnoughSignatures.into());
+    };
+    let legacy = now <= state.legacy_messages_until as UnixTimestamp;
+    let hash = message.hash();
+    let legacy_hash = if legacy { message.legacy_hash() } else { None };
+
+    let mut approvals = 0;
+    for slot in &set.signers {
+        for signer in slot.keys(now) {
+            let approved = match signer {
+                Signer::Secp256k1(key) => signatures.iter().any(|signature| {
+                    is_secp256k1_signer(&hash, signature, key)
+                        || legacy_hash.is_some_and(|legacy_hash| is_secp256k1_signer(&legacy_hash, signature, key))
+                }),
+                Signer::Ed25519(key) => is_ed25519_signer(&hash, key, instructions_sysvar)?,
+                Signer::Eip191(address) => {
+                    let eip191_hash = message.eip191_hash();
+                    signatures.iter().any(|signature| is_ethereum_signer(&eip191_hash, signature, address))
+                }
+                Signer::Eip712(address) => {
+                    let eip712_hash = message.eip712_hash();
+                    signatures.iter().any(|signature| is_ethereum_signer(&eip712_hash, signature, address))
+                }
+            };
+
+            if approved {
+                approvals += 1;
+                break;
+            }
+        }
+    }
+
+    if approvals < set.threshold {
+        let ed25519_only = set.signers.iter().all(|slot| matches!(slot.current, Signer::Ed25519(_)));
+        if ed25519_only {
+            msg!("Only {} of {} Ed25519 signers approved {:?}.", approvals, set.threshold, message.action());
+            return Err(CustomError::Ed25519InstructionNotFound.into());
+        }
+
+        msg!("Hash was: {:?}", hash.to_bytes());
+        msg!("Only {} of {} required signers approved.", approvals, set.threshold);
+        return Err(CustomError::NotEnoughSignatures.into());
+    }
+
+    Ok(())
+}
+
+fn is_secp256k1_signer(message_hash: &Hash,
+                       signature: &Signature,
+                       signer: &[u8; 33],
+) -> bool {
+    let Some(xy) = recover(&message_hash.to_bytes(), signature.rec_id, signature) else {
+        return false;
+    };
 
     // recovery returns XY coordinates
     // but compressed public key from secp256k1 keeps only X coordinate and Y parity
-    let xy = recovered.to_bytes();
     let y_parity = if xy[63] & 1 != 0 { 0x03 } else { 0x02 } as u8;
-    if xy[0..31] != state.signer[1..32] || y_parity != state.signer[0] {
-        msg!("Hash was: {:?}", message_hash.to_bytes());
-        msg!("Signature verification failed: {:?}", xy);
-        return Err(CustomError::WrongSignature.into());
+    xy[0..32] == signer[1..33] && y_parity == signer[0]
+}
+
+/// Ethereum address is the last 20 bytes of keccak256 of XY coordinates.
+fn is_ethereum_signer(message_hash: &keccak::Hash,
+                      signature: &Signature,
+                      address: &[u8; 20],
+) -> bool {
+    let rec_id = signature.rec_id.checked_sub(ETHEREUM_RECOVERY_ID_BASE).unwrap_or(signature.rec_id);
+    let Some(xy) = recover(&message_hash.to_bytes(), rec_id, signature) else {
+        return false;
+    };
+
+    keccak::hash(&xy).to_bytes()[12..32] == address[..]
+}
+
+fn recover(hash: &[u8], rec_id: u8, signature: &Signature) -> Option<[u8; 64]> {
+    secp256k1_recover(hash, rec_id, &signature.rs)
+        .map_err(|e| msg!("Recovery error: {}", e))
+        .ok()
+        .map(|recovered| recovered.to_bytes())
+}
+
+/// Looks for an Ed25519 program instruction before the current one, which verified the hash signed by the signer.
+/// Only offsets pointing to the Ed25519 instruction itself are taken into account.
+fn is_ed25519_signer(message_hash: &Hash,
+                     signer: &Pubkey,
+                     instructions_sysvar: &AccountInfo,
+) -> Result<bool, ProgramError> {
+    let current = load_current_index_checked(instructions_sysvar)?;
+
+    for index in 0..current {
+        let instruction = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if instruction.program_id != ed25519_program::id() {
+            continue;
+        }
+
+        let data = &instruction.data;
+        let count = *data.first().unwrap_or(&0) as usize;
+        for signature_index in 0..count {
+            let offsets = ED25519_HEADER + signature_index * ED25519_OFFSETS;
+            let read = |position: usize| -> Option<u16> {
+                let bytes = data.get(offsets + position * 2..offsets + position * 2 + 2)?;
+                Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+            };
+            let (
+                Some(public_key_offset), Some(public_key_instruction),
+                Some(message_offset), Some(message_size), Some(message_instruction),
+            ) = (read(2), read(3), read(4), read(5), read(6)) else {
+                break;
+            };
+
+            if public_key_instruction != ED25519_CURRENT_INSTRUCTION || message_instruction != ED25519_CURRENT_INSTRUCTION {
+                continue;
+            }
+
+            let public_key = data.get(public_key_offset as usize..public_key_offset as usize + 32);
+            let message = data.get(message_offset as usize..message_offset as usize + message_size as usize);
+
+            if public_key == Some(signer.as_ref()) && message == Some(message_hash.as_ref()) {
+                return Ok(true);
+            }
+        }
     }
 
-    Ok(())
-}
\ No newline at end of file
+    msg!("Ed25519 signature by {} wasn't found.", signer);
+    Ok(false)
+}
+
+#[cfg(test)]
+fn instructions_data(instructions: &[solana_program::instruction::Instruction], current: u16) -> Vec<u8> {
+    use solana_program::sysvar::instructions::{construct_instructions_data, store_current_index, BorrowedInstruction};
+
+    let borrowed: Vec<BorrowedInstruction> = instructions.iter()
+        .map(|instruction| BorrowedInstruction { program_id: &instruction.program_id, accounts: vec![], data: &instruction.data })
+        .collect();
+    let mut data = construct_instructions_data(&borrowed);
+    store_current_index(&mut data, current);
+    data
+}
+
+#[test]
+fn test_ed25519_signer() {
+    use solana_program::instruction::Instruction;
+    use solana_program::sysvar::instructions;
+
+    let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+    let public = ed25519_dalek::PublicKey::from(&secret);
+    let keypair = ed25519_dalek::Keypair { secret, public };
+    let signer = Pubkey::new_from_array(keypair.public.to_bytes());
+    let hash = solana_program::hash::hash(b"message");
+    let ed25519 = solana_sdk::ed25519_instruction::new_ed25519_instruction(&keypair, hash.as_ref());
+    let current = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+
+    let check = |instructions: &[Instruction], current: u16, hash: &Hash, signer: &Pubkey| -> bool {
+        let key = instructions::id();
+        let mut lamports = 0;
+        let mut data = instructions_data(instructions, current);
+        let owner = solana_program::sysvar::id();
+        let sysvar = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        is_ed25519_signer(hash, signer, &sysvar).unwrap()
+    };
+
+    assert!(check(&[ed25519.clone(), current.clone()], 1, &hash, &signer));
+    // only instructions before the current one are verified by the runtime first
+    assert!(!check(&[current.clone(), ed25519.clone()], 0, &hash, &signer));
+    assert!(!check(&[ed25519.clone(), current.clone()], 1, &hash, &Pubkey::new_unique()));
+    assert!(!check(&[ed25519.clone(), current.clone()], 1, &solana_program::hash::hash(b"other"), &signer));
+
+    // the key and the message must be read from the Ed25519 instruction itself
+    for position in [3, 6] {
+        let mut other = ed25519.clone();
+        let field = ED25519_HEADER + position * 2;
+        other.data[field..field + 2].copy_from_slice(&1u16.to_le_bytes());
+        assert!(!check(&[other, current.clone()], 1, &hash, &signer));
+    }
+
+    // the message or the offsets are cut off
+    for len in [ed25519.data.len() - 1, ED25519_HEADER + ED25519_OFFSETS - 1, 1, 0] {
+        let mut truncated = ed25519.clone();
+        truncated.data.truncate(len);
+        assert!(!check(&[truncated, current.clone()], 1, &hash, &signer));
+    }
+}
+
+#[cfg(test)]
+fn secp256k1_sign(secret: &libsecp256k1::SecretKey, hash: &Hash) -> Signature {
+    let (signature, rec_id) = libsecp256k1::sign(&libsecp256k1::Message::parse(&hash.to_bytes()), secret);
+    Signature { rec_id: rec_id.serialize(), rs: signature.serialize() }
+}
+
+#[test]
+fn test_threshold() {
+    use crate::message::{Action, MessageBody};
+    use crate::signer::{SignerSet, SignerSlot};
+
+    let secrets: Vec<libsecp256k1::SecretKey> = (1..=4u8)
+        .map(|i| libsecp256k1::SecretKey::parse(&[i; 32]).unwrap())
+        .collect();
+    let signer = |secret: &libsecp256k1::SecretKey| Signer::Secp256k1(libsecp256k1::PublicKey::from_secret_key(secret).serialize_compressed());
+
+    let message = Message {
+        program_id: Pubkey::new_unique(),
+        state: Pubkey::new_unique(),
+        lootbox_id: 1,
+        expire_at: 1000,
+        body: MessageBody::Obtain { vault: Pubkey::new_unique(), buyer: Pubkey::new_unique(), id: 7 },
+    };
+    let hash = message.hash();
+    let signatures: Vec<Signature> = secrets.iter().map(|secret| secp256k1_sign(secret, &hash)).collect();
+
+    // 2 of 3, the fourth key was rotated out of the first slot and is valid until 500
+    let mut state = crate::state::sample_state(Pubkey::new_unique(), signer(&secrets[0]), Pubkey::new_unique());
+    state.signer_sets = vec![SignerSet {
+        action: Action::Obtain,
+        signers: vec![
+            SignerSlot { current: signer(&secrets[0]), previous: Some(signer(&secrets[3])), previous_valid_until: 500 },
+            SignerSlot::new(signer(&secrets[1])),
+            SignerSlot::new(signer(&secrets[2])),
+        ],
+        threshold: 2,
+    }];
+
+    let key = solana_program::sysvar::instructions::id();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let owner = solana_program::sysvar::id();
+    let sysvar = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+    let check = |signatures: &[Signature], now: UnixTimestamp| check_approvals(&message, signatures, &state, now, &sysvar);
+
+    let not_enough = Err(CustomError::NotEnoughSignatures.into());
+    assert_eq!(check(&[], 100), not_enough);
+    assert_eq!(check(&signatures[1..2], 100), not_enough);
+    // the same signature counts once
+    assert_eq!(check(&[signatures[1].clone(), signatures[1].clone()], 100), not_enough);
+    // both keys of the rotated slot count once
+    assert_eq!(check(&[signatures[0].clone(), signatures[3].clone()], 100), not_enough);
+    // a signature of another message doesn't count
+    let other = secp256k1_sign(&secrets[2], &solana_program::hash::hash(b"other"));
+    assert_eq!(check(&[signatures[1].clone(), other], 100), not_enough);
+
+    assert_eq!(check(&signatures[1..3], 100), Ok(()));
+    assert_eq!(check(&[signatures[3].clone(), signatures[2].clone()], 100), Ok(()));
+    // the previous key doesn't vote after the cutoff
+    assert_eq!(check(&[signatures[3].clone(), signatures[2].clone()], 501), not_enough);
+    assert_eq!(check(&signatures, 501), Ok(()));
+}
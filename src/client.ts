@@ -70,7 +70,7 @@ async function main (argv: string[]) {
             break;
         case "withdraw": {
             if (argv.length != 7) {
-                throw new Error("Usage: npm run action withdraw <expiredAt> '[<ticketIds>,..]' '[{tokenMint: <mint>, amount: <amount>},..]' <signatureHex>.");
+                throw new Error("Usage: npm run action withdraw <expiredAt> '[<ticketIds>,..]' '[{tokenMint: <mint>, amount: <amount>},..]' <signatureHex>[,<signatureHex>..].");
             }
             let expiredAt = Number.parseInt(argv[3]);
             let ticketIdsRaw = JSON.parse(argv[4]);
@@ -88,7 +88,7 @@ async function main (argv: string[]) {
             }
             let rewards = rewardsRaw.map(o => new TokenAmount(o.tokenMint, o.amount)) as TokenAmount[];
 
-            let signature = parseSignature(argv[6]);
+            let signatures = argv[6].split(",").map(parseSignature);
             await withdraw(
                 connection,
                 PAYER,
@@ -97,7 +97,7 @@ async function main (argv: string[]) {
                 expiredAt,
                 ticketIds,
                 rewards,
-                signature
+                signatures
             );
             break;
         }
@@ -119,16 +119,21 @@ async function main (argv: string[]) {
         }
         case "obtain-ticket":
             if (argv.length != 6) {
-                throw new Error("Usage: npm run action obtain-ticket <ticketId> <expiredAt> <signatureHex>.");
+                throw new Error("Usage: npm run action obtain-ticket <ticketId> <expiredAt> <signatureHex>[,<signatureHex>..].");
             }
             let ticketId = Number.parseInt(argv[3]);
             let expiredAt = Number.parseInt(argv[4]);
-            let signature = parseSignature(argv[5]);
-            await obtain(connection, PARAMS.programId, PAYER, PARAMS.lootboxId, ticketId, expiredAt, signature);
+            let signatures = argv[5].split(",").map(parseSignature);
+            await obtain(connection, PARAMS.programId, PAYER, PARAMS.lootboxId, ticketId, expiredAt, signatures);
             break;
-        case "migrate":
-            await migrate(connection, PARAMS.programId, PARAMS.lootboxId);
+        case "migrate": {
+            if (argv.length != 4) {
+                throw new Error("Usage: npm run action migrate <legacyMessagesUntil>.");
+            }
+            let legacyMessagesUntil = Number.parseInt(argv[3]);
+            await migrate(connection, PARAMS.programId, PARAMS.lootboxId, legacyMessagesUntil);
             break;
+        }
         case "update-state":
             await updateState(connection, PARAMS.programId, PARAMS.lootboxId);
             break;
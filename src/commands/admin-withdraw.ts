@@ -8,7 +8,7 @@ import {
     TransactionInstruction,
 } from "@solana/web3.js";
 import {ADMIN, PAYER} from "../secrets";
-import {findStateAddress, loadState, VAULT_SEED} from "../state";
+import {findAccessAddress, findStateAddress, loadState, VAULT_SEED} from "../state";
 import {
     AdminWithdraw,
     serializeAdminWithdraw,
@@ -43,6 +43,7 @@ export async function adminWithdraw(connection: Connection, programId: PublicKey
 
     let source = spl.getAssociatedTokenAddressSync(tokenWithdraw.tokenMint, vaultPda, true);
     let destination = spl.getAssociatedTokenAddressSync(tokenWithdraw.tokenMint, ADMIN.publicKey, true);
+    let [accessPda] = findAccessAddress(ADMIN.publicKey, programId);
 
     let accounts: AccountMeta[] = [
         {pubkey: ADMIN.publicKey, isWritable: false, isSigner: true},
@@ -51,6 +52,8 @@ export async function adminWithdraw(connection: Connection, programId: PublicKey
         {pubkey: source, isWritable: true, isSigner: false},
         {pubkey: destination, isWritable: true, isSigner: false},
         {pubkey: spl.TOKEN_PROGRAM_ID, isWritable: false, isSigner: false},
+        {pubkey: tokenWithdraw.tokenMint, isWritable: false, isSigner: false},
+        {pubkey: accessPda, isWritable: false, isSigner: false},
     ];
 
     let withdrawInstruction = new TransactionInstruction({
@@ -7,7 +7,7 @@ import {
     TransactionInstruction
 } from "@solana/web3.js";
 import {ADMIN, PAYER} from "../secrets";
-import {findStateAddress, loadState, VAULT_SEED} from "../state";
+import {findConfigAddress, findStateAddress, findUserAddress, loadConfig, loadState, VAULT_SEED} from "../state";
 import {Buy, serializeBuy} from "../instruction";
 import * as spl from "@solana/spl-token";
 import {Ticket} from "../ticket";
@@ -27,6 +27,18 @@ export async function buy(connection: Connection, programId: PublicKey, buyer: K
     }
     let state = loadState(accountInfo.value);
 
+    let [configPda] = findConfigAddress(programId);
+    let configInfo = await connection.getParsedAccountInfo(configPda);
+    if (configInfo.value == null) {
+        throw new Error(`There is no account ${configPda}`);
+    }
+    let config = loadConfig(configInfo.value);
+    // the protocol fee goes to the treasury ATA of the payment token
+    let treasuryAta = spl.getAssociatedTokenAddressSync(paymentTokenMint, new PublicKey(config.treasury), true);
+    console.info(`Config: ${configPda}, fee: ${config.feeBps} bps, treasury ATA: ${treasuryAta}`);
+
+    let [userPda] = findUserAddress(statePda, buyer.publicKey, programId);
+
     // if a user has these token on the balance they must be on the ATA
     let payerAtaPub = spl.getAssociatedTokenAddressSync(paymentTokenMint, PAYER.publicKey);
     console.info(`Payer ATA: ${payerAtaPub}`);
@@ -36,10 +48,12 @@ export async function buy(connection: Connection, programId: PublicKey, buyer: K
 
     let ticketAmount = 20;
     let amount = 0;
+    let splits: PublicKey[] = [];
     for (let price of state.prices) {
         console.info(`Amount ${price.amount} sends to ${new PublicKey(price.ata)}`);
         if (new PublicKey(price.ata).equals(paymentAtaPub)) {
             amount = price.amount;
+            splits = price.splits.map(split => new PublicKey(split.account));
             break;
         }
     }
@@ -70,20 +84,29 @@ export async function buy(connection: Connection, programId: PublicKey, buyer: K
         console.info(`Ticket pda: ${ticketPda[0]} with bump ${ticketPda[1]}`)
     }
 
-    let buy = new Buy(lootboxId, ticketBumps, seed);
+    let buy = new Buy(lootboxId, ticketBumps, seed, paymentTokenMint.toBytes(), total);
 
     console.info(`buy: ${buy.instruction}, buy data: ${serializeBuy(buy).toString('hex')}`);
 
     tx.add(new TransactionInstruction({
             programId: programId,
             keys: [
-                {pubkey: PAYER.publicKey, isWritable: false, isSigner: true},
+                // writable, it pays for the user account
+                {pubkey: PAYER.publicKey, isWritable: true, isSigner: true},
                 {pubkey: payerAtaPub, isWritable: true, isSigner: false},
                 {pubkey: paymentAtaPub, isWritable: true, isSigner: false},
                 {pubkey: vaultPda, isWritable: true, isSigner: false},
                 {pubkey: statePda, isWritable: true, isSigner: false},
                 {pubkey: SystemProgram.programId, isWritable: false, isSigner: false},
                 {pubkey: spl.TOKEN_PROGRAM_ID, isWritable: false, isSigner: false},
+                {pubkey: paymentTokenMint, isWritable: false, isSigner: false},
+                {pubkey: configPda, isWritable: false, isSigner: false},
+                {pubkey: treasuryAta, isWritable: true, isSigner: false},
+                {pubkey: userPda, isWritable: true, isSigner: false},
+                // no referrer, so the split accounts of the price go first
+                ...splits.map((v) => {
+                    return {pubkey: v, isWritable: true, isSigner: false};
+                }),
                 ...ticketMints.map((v) => {
                     return {pubkey: v, isWritable: true, isSigner: false};
                 })
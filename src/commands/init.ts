@@ -8,14 +8,15 @@ import {
 } from "@solana/web3.js";
 import * as spl from "@solana/spl-token";
 import {ADMIN} from "../secrets";
-import {Initialize, serializeInitialize} from "../instruction";
-import {findStateAddress, loadState, VAULT_SEED} from "../state";
+import {Initialize, PriceParam, serializeInitialize} from "../instruction";
+import {findAccessAddress, findStateAddress, loadState, VAULT_SEED} from "../state";
 
 export async function init(connection: Connection, programId: PublicKey, lootboxId: number, signer: Buffer, paymentToken1: PublicKey, paymentToken2: PublicKey) {
     const blockhashInfo = await connection.getLatestBlockhash();
     let tx = new Transaction(blockhashInfo);
     let [vaultPda, vaultBump] = PublicKey.findProgramAddressSync([ADMIN.publicKey.toBytes(), Buffer.from(VAULT_SEED)], programId)
     let [statePda, stateBump] = findStateAddress(ADMIN.publicKey, lootboxId, programId);
+    let [accessPda] = findAccessAddress(ADMIN.publicKey, programId);
 
     console.info(`Vault: ${vaultPda}`);
     console.info(`State: ${statePda}`);
@@ -32,9 +33,9 @@ export async function init(connection: Connection, programId: PublicKey, lootbox
         2000,
         Math.floor(now.getTime() / 1000),
         Math.floor(later.getTime() / 1000),
-        new Uint8Array(signer),
+        {Secp256k1: new Uint8Array(signer)},
         "Solana Lootbox v2",
-        [5_000_000, 20_000_000],
+        [new PriceParam(5_000_000), new PriceParam(20_000_000)],
         ""
     );
 
@@ -71,10 +72,11 @@ export async function init(connection: Connection, programId: PublicKey, lootbox
         new TransactionInstruction({
             programId: programId,
             keys: [
-                {pubkey: ADMIN.publicKey, isWritable: false, isSigner: true},
+                {pubkey: ADMIN.publicKey, isWritable: true, isSigner: true},
                 {pubkey: vaultPda, isWritable: true, isSigner: false},
                 {pubkey: statePda, isWritable: true, isSigner: false},
                 {pubkey: SystemProgram.programId, isWritable: false, isSigner: false},
+                {pubkey: accessPda, isWritable: false, isSigner: false},
                 {pubkey: paymentAta1, isWritable: false, isSigner: false},
                 {pubkey: paymentAta2, isWritable: false, isSigner: false},
             ],
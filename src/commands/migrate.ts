@@ -2,14 +2,15 @@ import {
     Connection,
     PublicKey,
     sendAndConfirmTransaction,
+    SystemProgram,
     Transaction,
     TransactionInstruction
 } from "@solana/web3.js";
 import {ADMIN} from "../secrets";
-import {Migrate, serializeMigrate} from "../instruction";
+import {MigrateToV5, serializeMigrateToV5} from "../instruction";
 import {findStateAddress, loadState, STATE_SEED, VAULT_SEED} from "../state";
 
-export async function migrate(connection: Connection, programId: PublicKey, lootboxId: number) {
+export async function migrate(connection: Connection, programId: PublicKey, lootboxId: number, legacyMessagesUntil: number) {
     const blockhashInfo = await connection.getLatestBlockhash();
     let tx = new Transaction(blockhashInfo);
     let [vaultPda, vaultBump] = PublicKey.findProgramAddressSync([ADMIN.publicKey.toBytes(), Buffer.from(VAULT_SEED)], programId)
@@ -26,18 +27,22 @@ export async function migrate(connection: Connection, programId: PublicKey, loot
 
     console.info(`Vault: ${vaultPda}`);
 
-    let init = new Migrate(
+    let init = new MigrateToV5(
         stateBump,
+        lootboxId,
+        legacyMessagesUntil,
     );
 
     tx.add(
         new TransactionInstruction({
             programId: programId,
             keys: [
-                {pubkey: ADMIN.publicKey, isWritable: false, isSigner: true},
+                // writable, it pays for the state resize
+                {pubkey: ADMIN.publicKey, isWritable: true, isSigner: true},
                 {pubkey: statePda, isWritable: true, isSigner: false},
+                {pubkey: SystemProgram.programId, isWritable: false, isSigner: false},
             ],
-            data: Buffer.from(serializeMigrate(init)),
+            data: Buffer.from(serializeMigrateToV5(init)),
         })
     );
 
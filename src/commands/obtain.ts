@@ -4,11 +4,12 @@ import {
     PublicKey,
     sendAndConfirmTransaction,
     SystemProgram,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
     Transaction,
     TransactionInstruction
 } from "@solana/web3.js";
 import {ADMIN, PAYER} from "../secrets";
-import {findStateAddress, loadState, STATE_SEED, TICKET_SEED, VAULT_SEED} from "../state";
+import {findConfigAddress, findStateAddress, findUserAddress, loadState, STATE_SEED, TICKET_SEED, VAULT_SEED} from "../state";
 import {ObtainTicket, serializeObtainTicket, Signature} from "../instruction";
 import * as spl from "@solana/spl-token";
 import * as umiBundle from "@metaplex-foundation/umi-bundle-defaults";
@@ -18,7 +19,7 @@ import {fromWeb3JsKeypair, fromWeb3JsPublicKey, toWeb3JsPublicKey} from "@metapl
 import * as mpl from "@metaplex-foundation/mpl-token-metadata";
 import {Ticket} from "../ticket";
 
-export async function obtain(connection: Connection, programId: PublicKey, buyer: Keypair, lootboxId: number, ticketId: number, expiredAt: number, signature: Signature) {
+export async function obtain(connection: Connection, programId: PublicKey, buyer: Keypair, lootboxId: number, ticketId: number, expiredAt: number, signatures: Signature[]) {
     const blockhashInfo = await connection.getLatestBlockhash();
     // const balanceForRentExemption = await connection.getMinimumBalanceForRentExemption(0);
     let tx = new Transaction(blockhashInfo);
@@ -30,7 +31,11 @@ export async function obtain(connection: Connection, programId: PublicKey, buyer
     console.info(`Ticket Id: ${ticketId}, Expired At: ${expiredAt}`);
     let [ticketPda, ticketBump] = Ticket.findPDA(programId, buyer.publicKey, lootboxId, ticketId, 0);
     console.info(`Ticket: ${ticketPda}`);
-    console.info(`Signature recId: ${signature.recId}, rs: ${signature.rs}`);
+    for (let signature of signatures) {
+        console.info(`Signature recId: ${signature.recId}, rs: ${signature.rs}`);
+    }
+    let [configPda] = findConfigAddress(programId);
+    let [userPda] = findUserAddress(statePda, buyer.publicKey, programId);
 
     let accountInfo = await connection.getParsedAccountInfo(statePda);
     if (accountInfo.value == null) {
@@ -43,17 +48,20 @@ export async function obtain(connection: Connection, programId: PublicKey, buyer
         ticketBump,
         ticketId,
         expiredAt,
-        signature
+        signatures
     );
 
     tx.add(new TransactionInstruction({
             programId: programId,
             keys: [
-                {pubkey: buyer.publicKey, isWritable: false, isSigner: true},
+                {pubkey: buyer.publicKey, isWritable: true, isSigner: true},
                 {pubkey: statePda, isWritable: true, isSigner: false},
                 {pubkey: vaultPda[0], isWritable: false, isSigner: false},
                 {pubkey: ticketPda, isWritable: true, isSigner: false},
                 {pubkey: SystemProgram.programId, isWritable: false, isSigner: false},
+                {pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isWritable: false, isSigner: false},
+                {pubkey: configPda, isWritable: false, isSigner: false},
+                {pubkey: userPda, isWritable: true, isSigner: false},
             ],
             data: Buffer.from(serializeObtainTicket(instructionData)),
         }
@@ -7,7 +7,7 @@ import {
 } from "@solana/web3.js";
 import {ADMIN} from "../secrets";
 import {serializeUpdateState, UpdateState} from "../instruction";
-import {findStateAddress, loadState, VAULT_SEED} from "../state";
+import {findAccessAddress, findStateAddress, loadState, VAULT_SEED} from "../state";
 
 export async function updatePrice(connection: Connection, programId: PublicKey, lootboxId: number, paymentAta: PublicKey, amount: number) {
     const blockhashInfo = await connection.getLatestBlockhash();
@@ -15,6 +15,7 @@ export async function updatePrice(connection: Connection, programId: PublicKey,
     let [vaultPda, vaultBump] = PublicKey.findProgramAddressSync([ADMIN.publicKey.toBytes(), Buffer.from(VAULT_SEED)], programId)
     console.info(`Vault: ${vaultPda}`);
     let [statePda, stateBump] = findStateAddress(ADMIN.publicKey, lootboxId, programId);
+    let [accessPda] = findAccessAddress(ADMIN.publicKey, programId);
 
     {
         let data = await connection.getParsedAccountInfo(statePda);
@@ -42,6 +43,7 @@ export async function updatePrice(connection: Connection, programId: PublicKey,
             keys: [
                 {pubkey: ADMIN.publicKey, isWritable: false, isSigner: true},
                 {pubkey: statePda, isWritable: true, isSigner: false},
+                {pubkey: accessPda, isWritable: false, isSigner: false},
             ],
             data: Buffer.from(serializeUpdateState(params)),
         })
@@ -6,8 +6,8 @@ import {
     TransactionInstruction
 } from "@solana/web3.js";
 import {ADMIN} from "../secrets";
-import {Migrate, serializeMigrate, serializeUpdateState, UpdateState} from "../instruction";
-import {findStateAddress, loadState, STATE_SEED, VAULT_SEED} from "../state";
+import {serializeUpdateState, UpdateState} from "../instruction";
+import {findAccessAddress, findStateAddress, loadState, STATE_SEED, VAULT_SEED} from "../state";
 
 export async function updateState(connection: Connection, programId: PublicKey, lootboxId: number) {
     const blockhashInfo = await connection.getLatestBlockhash();
@@ -15,6 +15,7 @@ export async function updateState(connection: Connection, programId: PublicKey,
     let [vaultPda, vaultBump] = PublicKey.findProgramAddressSync([ADMIN.publicKey.toBytes(), Buffer.from(VAULT_SEED)], programId)
     console.info(`Vault: ${vaultPda}`);
     let [statePda, stateBump] = findStateAddress(ADMIN.publicKey, lootboxId, programId);
+    let [accessPda] = findAccessAddress(ADMIN.publicKey, programId);
 
     {
         let data = await connection.getParsedAccountInfo(statePda);
@@ -41,6 +42,7 @@ export async function updateState(connection: Connection, programId: PublicKey,
             keys: [
                 {pubkey: ADMIN.publicKey, isWritable: false, isSigner: true},
                 {pubkey: statePda, isWritable: true, isSigner: false},
+                {pubkey: accessPda, isWritable: false, isSigner: false},
             ],
             data: Buffer.from(serializeUpdateState(params)),
         })
@@ -3,11 +3,12 @@ import {
     PublicKey,
     sendAndConfirmTransaction,
     SystemProgram,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
     Transaction,
     TransactionInstruction
 } from "@solana/web3.js";
 import {ADMIN} from "../secrets";
-import {findStateAddress, loadState, VAULT_SEED} from "../state";
+import {findConfigAddress, findStateAddress, findUserAddress, loadState, loadUserState, VAULT_SEED} from "../state";
 import {serializeWithdraw, Signature, Withdraw} from "../instruction";
 import * as spl from "@solana/spl-token";
 import * as web3 from "@solana/web3.js";
@@ -26,7 +27,7 @@ export class TokenAmount {
 const ALT_ADDRESSES_LIMIT = 28;
 const MAX_TX_SIZE = web3.PACKET_DATA_SIZE;
 
-export async function withdraw(connection: Connection, owner: Keypair, programId: PublicKey, lootboxId: number, expiredAt: number, ticketIds: PublicKey[], tokenRewards: TokenAmount[], signature: Signature) {
+export async function withdraw(connection: Connection, owner: Keypair, programId: PublicKey, lootboxId: number, expiredAt: number, ticketIds: PublicKey[], tokenRewards: TokenAmount[], signatures: Signature[]) {
     let blockhashInfo = await connection.getLatestBlockhash();
 
     let [vaultPda, vaultBump] = PublicKey.findProgramAddressSync([ADMIN.publicKey.toBytes(), Buffer.from(VAULT_SEED)], programId);
@@ -40,12 +41,24 @@ export async function withdraw(connection: Connection, owner: Keypair, programId
     let state = loadState(accountInfo.value);
     console.info(`State: ${statePda}, withdrawCounter: ${state.withdrawCounter}`);
 
+    // the signed message is bound to the withdraw nonce of the user, the account is created on the first withdraw
+    let [userPda] = findUserAddress(statePda, owner.publicKey, programId);
+    let userInfo = await connection.getParsedAccountInfo(userPda);
+    let nonce = userInfo.value == null ? BigInt(0) : BigInt(loadUserState(userInfo.value).withdrawNonce);
+    console.info(`User: ${userPda}, nonce: ${nonce}`);
+
+    let [configPda] = findConfigAddress(programId);
+
     let accounts: AccountMeta[] = [
-        {pubkey: owner.publicKey, isWritable: false, isSigner: true},
+        {pubkey: owner.publicKey, isWritable: true, isSigner: true},
         {pubkey: vaultPda, isWritable: false, isSigner: false},
         {pubkey: statePda, isWritable: true, isSigner: false},
         {pubkey: SystemProgram.programId, isWritable: false, isSigner: false},
         {pubkey: spl.TOKEN_PROGRAM_ID, isWritable: false, isSigner: false},
+        {pubkey: spl.TOKEN_2022_PROGRAM_ID, isWritable: false, isSigner: false},
+        {pubkey: userPda, isWritable: true, isSigner: false},
+        {pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isWritable: false, isSigner: false},
+        {pubkey: configPda, isWritable: false, isSigner: false},
     ];
 
     for (let ticketMint of ticketIds) {
@@ -71,7 +84,8 @@ export async function withdraw(connection: Connection, owner: Keypair, programId
         ticketIds.length,
         amounts,
         expiredAt,
-        signature
+        signatures,
+        nonce,
     );
 
     let withdrawInstruction = new TransactionInstruction({
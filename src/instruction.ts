@@ -1,4 +1,19 @@
 import {BorshSchema, borshSerialize} from 'borsher';
+import {
+    Action,
+    PHASE_SCHEMA,
+    Phase,
+    PriceKind,
+    PUBKEY_SCHEMA,
+    RANDOMNESS_SCHEMA,
+    RandomnessConfig,
+    Role,
+    Signer,
+    SIGNER_SCHEMA,
+    Sku,
+    SKU_SCHEMA,
+    Split,
+} from "./state";
 
 // Определение enum типов
 enum InstructionType {
@@ -7,12 +22,49 @@ enum InstructionType {
     ObtainTicket,
     Buy = 4,
     Withdraw = 5,
+    Open = 6,
+    CommitOpen = 7,
+    RegisterReferrer = 8,
+    ExpireTicket = 9,
+    SetSkus = 236,
+    SetPhases = 237,
+    SetPriceSplits = 238,
+    UpdateConfig = 239,
+    InitializeConfig = 240,
+    RevokeRole = 241,
+    GrantRole = 242,
+    AcceptOwner = 243,
+    ProposeOwner = 244,
+    RotateSigner = 245,
+    RemoveSigner = 246,
+    AddSigner = 247,
+    RevealSeed = 248,
+    CommitSeed = 249,
+    SetRewards = 250,
+    MigrateToV5 = 251,
     UpdateState = 252,
-    Migrate = 253,
+    MigrateToV3 = 253,
     AdminWithdraw = 254,
     Initialize = 255,
 }
 
+const SLOT_HASHES_RANDOMNESS: RandomnessConfig = {SlotHashes: {delay: 0}};
+
+export class PriceParam {
+    static readonly SCHEMA = BorshSchema.Struct({
+        amount: BorshSchema.u64,
+        kind: BorshSchema.u8,
+    });
+
+    amount: number;
+    kind: PriceKind;
+
+    constructor(amount: number, kind: PriceKind = PriceKind.Token) {
+        this.amount = amount;
+        this.kind = kind;
+    }
+}
+
 export class Initialize {
     static readonly SCHEMA = BorshSchema.Struct({
         instruction: BorshSchema.u8,
@@ -22,10 +74,11 @@ export class Initialize {
         maxSupply: BorshSchema.u32,
         beginTs: BorshSchema.u32,
         endTs: BorshSchema.u32,
-        signer: BorshSchema.Array(BorshSchema.u8, 33),
+        signer: SIGNER_SCHEMA,
         name: BorshSchema.String,
-        prices: BorshSchema.Vec(BorshSchema.u64),
+        prices: BorshSchema.Vec(PriceParam.SCHEMA),
         baseUrl: BorshSchema.String,
+        randomness: RANDOMNESS_SCHEMA,
     });
 
     instruction: InstructionType = InstructionType.Initialize;
@@ -35,12 +88,13 @@ export class Initialize {
     maxSupply: number;
     beginTs: number;
     endTs: number;
-    signer: Uint8Array;
+    signer: Signer;
     name: string;
-    prices: number[];
+    prices: PriceParam[];
     baseUrl: string;
+    randomness: RandomnessConfig;
 
-    constructor(lootboxId: number, vaultBump: number, stateBump: number, maxSupply: number, beginTs: number, endTs: number, signer: Uint8Array, name: string, prices: number[], baseUrl: string) {
+    constructor(lootboxId: number, vaultBump: number, stateBump: number, maxSupply: number, beginTs: number, endTs: number, signer: Signer, name: string, prices: PriceParam[], baseUrl: string, randomness: RandomnessConfig = SLOT_HASHES_RANDOMNESS) {
         this.lootboxId = lootboxId;
         this.vaultBump = vaultBump;
         this.stateBump = stateBump;
@@ -51,26 +105,56 @@ export class Initialize {
         this.name = name;
         this.prices = prices;
         this.baseUrl = baseUrl;
+        this.randomness = randomness;
     }
 }
 
-export class Migrate {
+export class MigrateToV3 {
     static readonly SCHEMA = BorshSchema.Struct({
         instruction: BorshSchema.u8,
         stateBump: BorshSchema.u8,
     });
 
-    instruction: InstructionType = InstructionType.Migrate;
+    instruction: InstructionType = InstructionType.MigrateToV3;
     stateBump: number
 
     constructor(stateBump: number) {
         this.stateBump = stateBump;
     }
 }
-const MAX_SUPPLY: u32 = 1;
-const BEGIN_TS: u32 = 2;
-const END_TS: u32 = 4;
-const PRICE: u32 = 8;
+
+export class MigrateToV5 {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        stateBump: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+        legacyMessagesUntil: BorshSchema.u32,
+    });
+
+    instruction: InstructionType = InstructionType.MigrateToV5;
+    stateBump: number;
+    lootboxId: number;
+    legacyMessagesUntil: number; // the backends keep signing the old messages until they are upgraded
+
+    constructor(stateBump: number, lootboxId: number, legacyMessagesUntil: number) {
+        this.stateBump = stateBump;
+        this.lootboxId = lootboxId;
+        this.legacyMessagesUntil = legacyMessagesUntil;
+    }
+}
+
+const MAX_SUPPLY = 1;
+const BEGIN_TS = 2;
+const END_TS = 4;
+const PRICE = 8;
+const NEW_PRICE = 16;
+const RANDOMNESS = 32;
+const LEGACY_MESSAGES_UNTIL = 64;
+const PAUSED = 128;
+const REFERRAL = 256;
+const WALLET_LIMITS = 512;
+const ALLOWLIST = 1024;
+const DROP_SCHEDULE = 2048;
 
 export class UpdateState {
     static readonly SCHEMA = BorshSchema.Struct({
@@ -81,8 +165,22 @@ export class UpdateState {
         maxSupply: BorshSchema.u32,
         beginTs: BorshSchema.u32,
         endTs: BorshSchema.u32,
-        priceAta: BorshSchema.Array(BorshSchema.u8, 32),
+        priceAta: PUBKEY_SCHEMA,
         priceAmount: BorshSchema.u64,
+        priceKind: BorshSchema.u8,
+        randomness: RANDOMNESS_SCHEMA,
+        legacyMessagesUntil: BorshSchema.u32,
+        paused: BorshSchema.u8,
+        referralBps: BorshSchema.u16,
+        referralRegistration: BorshSchema.bool,
+        maxTicketsPerWallet: BorshSchema.u32,
+        windowLimit: BorshSchema.u32,
+        windowDuration: BorshSchema.u32,
+        allowlistRoot: BorshSchema.Option(PUBKEY_SCHEMA),
+        dropPeriod: BorshSchema.u32,
+        dropStartTs: BorshSchema.u32,
+        dropDuration: BorshSchema.u32,
+        dropSupply: BorshSchema.u32,
     });
 
     instruction: InstructionType = InstructionType.UpdateState;
@@ -92,8 +190,22 @@ export class UpdateState {
     maxSupply: number = 0;
     beginTs: number = 0;
     endTs: number = 0;
-    priceAta: Uint8Array = [];
+    priceAta: Uint8Array = new Uint8Array(32);
     priceAmount: number = 0;
+    priceKind: PriceKind = PriceKind.Token;
+    randomness: RandomnessConfig = SLOT_HASHES_RANDOMNESS;
+    legacyMessagesUntil: number = 0;
+    paused: number = 0;
+    referralBps: number = 0;
+    referralRegistration: boolean = false;
+    maxTicketsPerWallet: number = 0;
+    windowLimit: number = 0;
+    windowDuration: number = 0;
+    allowlistRoot: Uint8Array | null = null;
+    dropPeriod: number = 0;
+    dropStartTs: number = 0;
+    dropDuration: number = 0;
+    dropSupply: number = 0;
 
     constructor(lootboxId: number, stateBump: number) {
         this.lootboxId = lootboxId;
@@ -124,6 +236,77 @@ export class UpdateState {
         this.enabledFields |= PRICE;
         return this;
     }
+
+    public withNewPrice(ata: Uint8Array, amount: number, kind: PriceKind) : UpdateState {
+        this.priceAta = ata;
+        this.priceAmount = amount;
+        this.priceKind = kind;
+        this.enabledFields |= NEW_PRICE;
+        return this;
+    }
+
+    public withRandomness(value: RandomnessConfig) : UpdateState {
+        this.randomness = value;
+        this.enabledFields |= RANDOMNESS;
+        return this;
+    }
+
+    public withLegacyMessagesUntil(value: number) : UpdateState {
+        this.legacyMessagesUntil = value;
+        this.enabledFields |= LEGACY_MESSAGES_UNTIL;
+        return this;
+    }
+
+    public withPaused(value: number) : UpdateState {
+        this.paused = value;
+        this.enabledFields |= PAUSED;
+        return this;
+    }
+
+    public withReferral(bps: number, registration: boolean) : UpdateState {
+        this.referralBps = bps;
+        this.referralRegistration = registration;
+        this.enabledFields |= REFERRAL;
+        return this;
+    }
+
+    public withWalletLimits(maxTicketsPerWallet: number, windowLimit: number, windowDuration: number) : UpdateState {
+        this.maxTicketsPerWallet = maxTicketsPerWallet;
+        this.windowLimit = windowLimit;
+        this.windowDuration = windowDuration;
+        this.enabledFields |= WALLET_LIMITS;
+        return this;
+    }
+
+    public withAllowlist(root: Uint8Array | null) : UpdateState {
+        this.allowlistRoot = root;
+        this.enabledFields |= ALLOWLIST;
+        return this;
+    }
+
+    public withDropSchedule(period: number, startTs: number, duration: number, supply: number) : UpdateState {
+        this.dropPeriod = period;
+        this.dropStartTs = startTs;
+        this.dropDuration = duration;
+        this.dropSupply = supply;
+        this.enabledFields |= DROP_SCHEDULE;
+        return this;
+    }
+}
+
+export class AllowlistProof {
+    static readonly SCHEMA = BorshSchema.Struct({
+        allocation: BorshSchema.u32,
+        proof: BorshSchema.Vec(PUBKEY_SCHEMA),
+    });
+
+    allocation: number; // lifetime tickets of the wallet, 0 is unlimited
+    proof: Uint8Array[];
+
+    constructor(allocation: number, proof: Uint8Array[]) {
+        this.allocation = allocation;
+        this.proof = proof;
+    }
 }
 
 export class Buy {
@@ -132,17 +315,32 @@ export class Buy {
         lootboxId: BorshSchema.u16,
         ticketBumps: BorshSchema.Vec(BorshSchema.u8),
         ticketSeed: BorshSchema.u32,
+        referrer: BorshSchema.Option(PUBKEY_SCHEMA),
+        expectedMint: PUBKEY_SCHEMA,
+        maxTotal: BorshSchema.u64,
+        allowlist: BorshSchema.Option(AllowlistProof.SCHEMA),
+        sku: BorshSchema.Option(BorshSchema.u8),
     });
 
     instruction: InstructionType = InstructionType.Buy;
     lootboxId: number;
     ticketBumps: Array<number>;
     ticketSeed: number;
+    referrer: Uint8Array | null;
+    expectedMint: Uint8Array; // zeros (system program) for native prices
+    maxTotal: bigint; // price * count the buyer agrees to pay
+    allowlist: AllowlistProof | null;
+    sku: number | null;
 
-    constructor(lootboxId: number, ticketBumps: Array<number>, ticketSeed: number) {
+    constructor(lootboxId: number, ticketBumps: Array<number>, ticketSeed: number, expectedMint: Uint8Array, maxTotal: bigint, referrer: Uint8Array | null = null, allowlist: AllowlistProof | null = null, sku: number | null = null) {
         this.lootboxId = lootboxId;
         this.ticketBumps = ticketBumps;
         this.ticketSeed = ticketSeed;
+        this.expectedMint = expectedMint;
+        this.maxTotal = maxTotal;
+        this.referrer = referrer;
+        this.allowlist = allowlist;
+        this.sku = sku;
     }
 }
 
@@ -169,7 +367,7 @@ export class ObtainTicket {
         ticketBump: BorshSchema.u8,
         ticketId: BorshSchema.u32,
         expireAt: BorshSchema.u32,
-        signature: Signature.SCHEMA,
+        signatures: BorshSchema.Vec(Signature.SCHEMA),
     });
 
     instruction: InstructionType = InstructionType.ObtainTicket;
@@ -177,14 +375,14 @@ export class ObtainTicket {
     ticketBump: number;
     ticketId: number;
     expireAt: number;
-    signature: Signature;
+    signatures: Signature[];
 
-    constructor(lootboxId: number, ticketBump: number, ticketId: number, expireAt: number, signature: Signature) {
+    constructor(lootboxId: number, ticketBump: number, ticketId: number, expireAt: number, signatures: Signature[]) {
         this.lootboxId = lootboxId;
         this.ticketBump = ticketBump;
         this.ticketId = ticketId;
         this.expireAt = expireAt;
-        this.signature = signature;
+        this.signatures = signatures;
     }
 }
 
@@ -193,9 +391,11 @@ export class Withdraw {
         instruction: BorshSchema.u8,
         lootboxId: BorshSchema.u16,
         expireAt: BorshSchema.u32,
-        signature: Signature.SCHEMA,
+        signatures: BorshSchema.Vec(Signature.SCHEMA),
         tickets: BorshSchema.u8,
         amounts: BorshSchema.Vec(BorshSchema.u64),
+        nonce: BorshSchema.u64,
+        rolls: BorshSchema.Vec(BorshSchema.u64),
     });
 
     instruction: InstructionType = InstructionType.Withdraw;
@@ -203,14 +403,54 @@ export class Withdraw {
     tickets: number;
     amounts: number[];
     expireAt: number;
-    signature: Signature;
+    signatures: Signature[]; // Ed25519 signers are verified through the instructions sysvar instead
+    nonce: bigint; // must be equal to the user account withdrawNonce
+    rolls: bigint[]; // rolls of every ticket while the server seed is committed
 
-    constructor(lootboxId: number, tickets: number, amounts: number[], expireAt: number, signature: Signature) {
+    constructor(lootboxId: number, tickets: number, amounts: number[], expireAt: number, signatures: Signature[], nonce: bigint, rolls: bigint[] = []) {
         this.lootboxId = lootboxId;
         this.tickets = tickets;
         this.amounts = amounts;
         this.expireAt = expireAt;
-        this.signature = signature;
+        this.signatures = signatures;
+        this.nonce = nonce;
+        this.rolls = rolls;
+    }
+}
+
+// Open, CommitOpen, ExpireTicket, AcceptOwner and RegisterReferrer have only the lootbox id
+export class LootboxInstruction {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+    });
+
+    instruction: InstructionType;
+    lootboxId: number;
+
+    constructor(instruction: InstructionType, lootboxId: number) {
+        this.instruction = instruction;
+        this.lootboxId = lootboxId;
+    }
+
+    public static open(lootboxId: number): LootboxInstruction {
+        return new LootboxInstruction(InstructionType.Open, lootboxId);
+    }
+
+    public static commitOpen(lootboxId: number): LootboxInstruction {
+        return new LootboxInstruction(InstructionType.CommitOpen, lootboxId);
+    }
+
+    public static expireTicket(lootboxId: number): LootboxInstruction {
+        return new LootboxInstruction(InstructionType.ExpireTicket, lootboxId);
+    }
+
+    public static acceptOwner(lootboxId: number): LootboxInstruction {
+        return new LootboxInstruction(InstructionType.AcceptOwner, lootboxId);
+    }
+
+    public static registerReferrer(lootboxId: number): LootboxInstruction {
+        return new LootboxInstruction(InstructionType.RegisterReferrer, lootboxId);
     }
 }
 
@@ -231,6 +471,290 @@ export class AdminWithdraw {
     }
 }
 
+export class RewardEntry {
+    static readonly SCHEMA = BorshSchema.Struct({
+        mint: PUBKEY_SCHEMA,
+        amount: BorshSchema.u64,
+        weight: BorshSchema.u32,
+        stock: BorshSchema.u32,
+    });
+
+    mint: Uint8Array;
+    amount: number;
+    weight: number;
+    stock: number; // how many times the entry can be won
+
+    constructor(mint: Uint8Array, amount: number, weight: number, stock: number) {
+        this.mint = mint;
+        this.amount = amount;
+        this.weight = weight;
+        this.stock = stock;
+    }
+}
+
+export class SetRewards {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+        rewardsBump: BorshSchema.u8,
+        entries: BorshSchema.Vec(RewardEntry.SCHEMA),
+    });
+
+    instruction: InstructionType = InstructionType.SetRewards;
+    lootboxId: number;
+    rewardsBump: number;
+    entries: RewardEntry[];
+
+    constructor(lootboxId: number, rewardsBump: number, entries: RewardEntry[]) {
+        this.lootboxId = lootboxId;
+        this.rewardsBump = rewardsBump;
+        this.entries = entries;
+    }
+}
+
+// CommitSeed takes the hash of the seed, RevealSeed takes the seed itself
+export class ServerSeed {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+        seed: PUBKEY_SCHEMA,
+    });
+
+    instruction: InstructionType;
+    lootboxId: number;
+    seed: Uint8Array;
+
+    constructor(instruction: InstructionType.CommitSeed | InstructionType.RevealSeed, lootboxId: number, seed: Uint8Array) {
+        this.instruction = instruction;
+        this.lootboxId = lootboxId;
+        this.seed = seed;
+    }
+
+    public static commit(lootboxId: number, seedHash: Uint8Array): ServerSeed {
+        return new ServerSeed(InstructionType.CommitSeed, lootboxId, seedHash);
+    }
+
+    public static reveal(lootboxId: number, seed: Uint8Array): ServerSeed {
+        return new ServerSeed(InstructionType.RevealSeed, lootboxId, seed);
+    }
+}
+
+export class SignerParams {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+        action: BorshSchema.u8,
+        signer: SIGNER_SCHEMA,
+        threshold: BorshSchema.u8,
+    });
+
+    instruction: InstructionType;
+    lootboxId: number;
+    action: Action;
+    signer: Signer;
+    threshold: number;
+
+    constructor(instruction: InstructionType.AddSigner | InstructionType.RemoveSigner, lootboxId: number, action: Action, signer: Signer, threshold: number) {
+        this.instruction = instruction;
+        this.lootboxId = lootboxId;
+        this.action = action;
+        this.signer = signer;
+        this.threshold = threshold;
+    }
+
+    public static add(lootboxId: number, action: Action, signer: Signer, threshold: number): SignerParams {
+        return new SignerParams(InstructionType.AddSigner, lootboxId, action, signer, threshold);
+    }
+
+    public static remove(lootboxId: number, action: Action, signer: Signer, threshold: number): SignerParams {
+        return new SignerParams(InstructionType.RemoveSigner, lootboxId, action, signer, threshold);
+    }
+}
+
+export class RotateSigner {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+        action: BorshSchema.u8,
+        signer: SIGNER_SCHEMA,
+        newSigner: SIGNER_SCHEMA,
+        previousValidUntil: BorshSchema.u32,
+    });
+
+    instruction: InstructionType = InstructionType.RotateSigner;
+    lootboxId: number;
+    action: Action;
+    signer: Signer;
+    newSigner: Signer;
+    previousValidUntil: number;
+
+    constructor(lootboxId: number, action: Action, signer: Signer, newSigner: Signer, previousValidUntil: number) {
+        this.lootboxId = lootboxId;
+        this.action = action;
+        this.signer = signer;
+        this.newSigner = newSigner;
+        this.previousValidUntil = previousValidUntil;
+    }
+}
+
+export class ProposeOwner {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+        newOwner: BorshSchema.Option(PUBKEY_SCHEMA),
+    });
+
+    instruction: InstructionType = InstructionType.ProposeOwner;
+    lootboxId: number;
+    newOwner: Uint8Array | null; // null cancels the proposal
+
+    constructor(lootboxId: number, newOwner: Uint8Array | null) {
+        this.lootboxId = lootboxId;
+        this.newOwner = newOwner;
+    }
+}
+
+export class RoleParams {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+        account: PUBKEY_SCHEMA,
+        roles: BorshSchema.u8,
+    });
+
+    instruction: InstructionType;
+    lootboxId: number;
+    account: Uint8Array;
+    roles: number; // Role bits to grant or revoke
+
+    constructor(instruction: InstructionType.GrantRole | InstructionType.RevokeRole, lootboxId: number, account: Uint8Array, roles: number) {
+        this.instruction = instruction;
+        this.lootboxId = lootboxId;
+        this.account = account;
+        this.roles = roles;
+    }
+
+    public static grant(lootboxId: number, account: Uint8Array, ...roles: Role[]): RoleParams {
+        return new RoleParams(InstructionType.GrantRole, lootboxId, account, roles.reduce((bits, role) => bits | role, 0));
+    }
+
+    public static revoke(lootboxId: number, account: Uint8Array, ...roles: Role[]): RoleParams {
+        return new RoleParams(InstructionType.RevokeRole, lootboxId, account, roles.reduce((bits, role) => bits | role, 0));
+    }
+}
+
+export class InitializeConfig {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+    });
+
+    instruction: InstructionType = InstructionType.InitializeConfig;
+}
+
+const CONFIG_PAUSED = 1;
+const CONFIG_FEE = 2;
+const CONFIG_ALLOWED_MINTS = 4;
+const CONFIG_MAX_TICKETS_PER_BUY = 8;
+
+export class UpdateConfig {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        enabledFields: BorshSchema.u32,
+        paused: BorshSchema.bool,
+        feeBps: BorshSchema.u16,
+        treasury: PUBKEY_SCHEMA,
+        allowedMints: BorshSchema.Vec(PUBKEY_SCHEMA),
+        maxTicketsPerBuy: BorshSchema.u8,
+    });
+
+    instruction: InstructionType = InstructionType.UpdateConfig;
+    enabledFields: number = 0;
+    paused: boolean = false;
+    feeBps: number = 0;
+    treasury: Uint8Array = new Uint8Array(32);
+    allowedMints: Uint8Array[] = [];
+    maxTicketsPerBuy: number = 0;
+
+    public withPaused(value: boolean) : UpdateConfig {
+        this.paused = value;
+        this.enabledFields |= CONFIG_PAUSED;
+        return this;
+    }
+
+    public withFee(bps: number, treasury: Uint8Array) : UpdateConfig {
+        this.feeBps = bps;
+        this.treasury = treasury;
+        this.enabledFields |= CONFIG_FEE;
+        return this;
+    }
+
+    public withAllowedMints(value: Uint8Array[]) : UpdateConfig {
+        this.allowedMints = value;
+        this.enabledFields |= CONFIG_ALLOWED_MINTS;
+        return this;
+    }
+
+    public withMaxTicketsPerBuy(value: number) : UpdateConfig {
+        this.maxTicketsPerBuy = value;
+        this.enabledFields |= CONFIG_MAX_TICKETS_PER_BUY;
+        return this;
+    }
+}
+
+export class SetPriceSplits {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+        priceAta: PUBKEY_SCHEMA,
+        splits: BorshSchema.Vec(Split.SCHEMA),
+    });
+
+    instruction: InstructionType = InstructionType.SetPriceSplits;
+    lootboxId: number;
+    priceAta: Uint8Array;
+    splits: Split[];
+
+    constructor(lootboxId: number, priceAta: Uint8Array, splits: Split[]) {
+        this.lootboxId = lootboxId;
+        this.priceAta = priceAta;
+        this.splits = splits;
+    }
+}
+
+export class SetPhases {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+        phases: BorshSchema.Vec(PHASE_SCHEMA),
+    });
+
+    instruction: InstructionType = InstructionType.SetPhases;
+    lootboxId: number;
+    phases: Phase[];
+
+    constructor(lootboxId: number, phases: Phase[]) {
+        this.lootboxId = lootboxId;
+        this.phases = phases;
+    }
+}
+
+export class SetSkus {
+    static readonly SCHEMA = BorshSchema.Struct({
+        instruction: BorshSchema.u8,
+        lootboxId: BorshSchema.u16,
+        skus: BorshSchema.Vec(SKU_SCHEMA),
+    });
+
+    instruction: InstructionType = InstructionType.SetSkus;
+    lootboxId: number;
+    skus: Sku[];
+
+    constructor(lootboxId: number, skus: Sku[]) {
+        this.lootboxId = lootboxId;
+        this.skus = skus;
+    }
+}
+
 export function serializeInitialize(instruction: Initialize): Uint8Array {
     return borshSerialize(Initialize.SCHEMA, instruction);
 }
@@ -247,8 +771,16 @@ export function serializeWithdraw(instruction: Withdraw): Uint8Array {
     return borshSerialize(Withdraw.SCHEMA, instruction);
 }
 
-export function serializeMigrate(instruction: Migrate): Uint8Array {
-    return borshSerialize(Migrate.SCHEMA, instruction);
+export function serializeLootboxInstruction(instruction: LootboxInstruction): Uint8Array {
+    return borshSerialize(LootboxInstruction.SCHEMA, instruction);
+}
+
+export function serializeMigrateToV3(instruction: MigrateToV3): Uint8Array {
+    return borshSerialize(MigrateToV3.SCHEMA, instruction);
+}
+
+export function serializeMigrateToV5(instruction: MigrateToV5): Uint8Array {
+    return borshSerialize(MigrateToV5.SCHEMA, instruction);
 }
 
 export function serializeUpdateState(instruction: UpdateState): Uint8Array {
@@ -257,4 +789,48 @@ export function serializeUpdateState(instruction: UpdateState): Uint8Array {
 
 export function serializeAdminWithdraw(instruction: AdminWithdraw): Uint8Array {
     return borshSerialize(AdminWithdraw.SCHEMA, instruction);
-}
\ No newline at end of file
+}
+
+export function serializeSetRewards(instruction: SetRewards): Uint8Array {
+    return borshSerialize(SetRewards.SCHEMA, instruction);
+}
+
+export function serializeServerSeed(instruction: ServerSeed): Uint8Array {
+    return borshSerialize(ServerSeed.SCHEMA, instruction);
+}
+
+export function serializeSignerParams(instruction: SignerParams): Uint8Array {
+    return borshSerialize(SignerParams.SCHEMA, instruction);
+}
+
+export function serializeRotateSigner(instruction: RotateSigner): Uint8Array {
+    return borshSerialize(RotateSigner.SCHEMA, instruction);
+}
+
+export function serializeProposeOwner(instruction: ProposeOwner): Uint8Array {
+    return borshSerialize(ProposeOwner.SCHEMA, instruction);
+}
+
+export function serializeRoleParams(instruction: RoleParams): Uint8Array {
+    return borshSerialize(RoleParams.SCHEMA, instruction);
+}
+
+export function serializeInitializeConfig(instruction: InitializeConfig): Uint8Array {
+    return borshSerialize(InitializeConfig.SCHEMA, instruction);
+}
+
+export function serializeUpdateConfig(instruction: UpdateConfig): Uint8Array {
+    return borshSerialize(UpdateConfig.SCHEMA, instruction);
+}
+
+export function serializeSetPriceSplits(instruction: SetPriceSplits): Uint8Array {
+    return borshSerialize(SetPriceSplits.SCHEMA, instruction);
+}
+
+export function serializeSetPhases(instruction: SetPhases): Uint8Array {
+    return borshSerialize(SetPhases.SCHEMA, instruction);
+}
+
+export function serializeSetSkus(instruction: SetSkus): Uint8Array {
+    return borshSerialize(SetSkus.SCHEMA, instruction);
+}
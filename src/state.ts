@@ -4,75 +4,294 @@ import {AccountInfo, ParsedAccountData, PublicKey} from "@solana/web3.js";
 export const STATE_SEED = "state";
 export const VAULT_SEED = "vault";
 export const TICKET_SEED = "ticket";
+export const USER_SEED = "user";
+export const CONFIG_SEED = "config";
+export const ACCESS_SEED = "access";
+export const REFERRER_SEED = "referrer";
+export const REWARDS_SEED = "rewards";
 
 export enum StateVersion {
     Version1 = 1,
     Version2,
     Version3,
     Version4,
+    Version5,
+}
+
+export enum PriceKind {
+    Token = 0,
+    Native = 1,
+}
+
+export enum Action {
+    Obtain = 1,
+    Withdraw = 2,
+}
+
+export const PUBKEY_SCHEMA = BorshSchema.Array(BorshSchema.u8, 32);
+
+export class Split {
+    public static readonly SCHEMA = BorshSchema.Struct({
+        account: PUBKEY_SCHEMA,
+        bps: BorshSchema.u16,
+    })
+
+    account: Uint8Array;
+    bps: number;
+
+    constructor(account: Uint8Array, bps: number) {
+        this.account = account;
+        this.bps = bps;
+    }
 }
 
 export class Price {
     public static readonly SCHEMA = BorshSchema.Struct({
         amount: BorshSchema.u64,
-        ata: BorshSchema.Array(BorshSchema.u8, 32),
+        ata: PUBKEY_SCHEMA,
+        kind: BorshSchema.u8,
+        splits: BorshSchema.Vec(Split.SCHEMA),
     })
 
     amount: number;
     ata: Uint8Array;
+    kind: PriceKind;
+    splits: Split[];
 
-    constructor(amount: number, ata: Uint8Array) {
+    constructor(amount: number, ata: Uint8Array, kind: PriceKind = PriceKind.Token, splits: Split[] = []) {
         this.amount = amount;
         this.ata = ata;
+        this.kind = kind;
+        this.splits = splits;
     }
 }
 
+// one of the keys is set, e.g. {Secp256k1: <33 bytes>} or {Ed25519: <32 bytes>}
+export type Signer = {Secp256k1?: Uint8Array, Ed25519?: Uint8Array, Eip191?: Uint8Array, Eip712?: Uint8Array};
+
+export const SIGNER_SCHEMA = BorshSchema.Enum({
+    Secp256k1: BorshSchema.Array(BorshSchema.u8, 33),
+    Ed25519: PUBKEY_SCHEMA,
+    Eip191: BorshSchema.Array(BorshSchema.u8, 20),
+    Eip712: BorshSchema.Array(BorshSchema.u8, 20),
+});
+
+export type SignerSlot = {current: Signer, previous: Signer | null, previousValidUntil: number};
+
+export type SignerSet = {action: Action, signers: SignerSlot[], threshold: number};
+
+const SIGNER_SET_SCHEMA = BorshSchema.Struct({
+    action: BorshSchema.u8,
+    signers: BorshSchema.Vec(BorshSchema.Struct({
+        current: SIGNER_SCHEMA,
+        previous: BorshSchema.Option(SIGNER_SCHEMA),
+        previousValidUntil: BorshSchema.u32,
+    })),
+    threshold: BorshSchema.u8,
+});
+
+// {SlotHashes: {delay}} or {Vrf: {oracleProgram, seedOffset, slotOffset, resultOffset}}
+export type RandomnessConfig = {
+    SlotHashes?: {delay: number},
+    Vrf?: {oracleProgram: Uint8Array, seedOffset: number, slotOffset: number, resultOffset: number},
+};
+
+export const RANDOMNESS_SCHEMA = BorshSchema.Enum({
+    SlotHashes: BorshSchema.Struct({
+        delay: BorshSchema.u16,
+    }),
+    Vrf: BorshSchema.Struct({
+        oracleProgram: PUBKEY_SCHEMA,
+        seedOffset: BorshSchema.u16,
+        slotOffset: BorshSchema.u16,
+        resultOffset: BorshSchema.u16,
+    }),
+});
+
+export type PriceOverride = {ata: Uint8Array, amount: number};
+
+// {Public: {}} or {Allowlist: {root}}
+export type Eligibility = {Public?: {}, Allowlist?: {root: Uint8Array}};
+
+export type Phase = {
+    beginTs: number,
+    endTs: number,
+    priceOverrides: PriceOverride[],
+    maxSupply: number,
+    issued: number,
+    eligibility: Eligibility,
+};
+
+export const PHASE_SCHEMA = BorshSchema.Struct({
+    beginTs: BorshSchema.u32,
+    endTs: BorshSchema.u32,
+    priceOverrides: BorshSchema.Vec(BorshSchema.Struct({
+        ata: PUBKEY_SCHEMA,
+        amount: BorshSchema.u64,
+    })),
+    maxSupply: BorshSchema.u32,
+    issued: BorshSchema.u32,
+    eligibility: BorshSchema.Enum({
+        Public: BorshSchema.Unit,
+        Allowlist: BorshSchema.Struct({
+            root: PUBKEY_SCHEMA,
+        }),
+    }),
+});
+
+export type DropSchedule = {startTs: number, period: number, duration: number, supply: number, windowStart: number, issued: number};
+
+const DROP_SCHEDULE_SCHEMA = BorshSchema.Struct({
+    startTs: BorshSchema.u32,
+    period: BorshSchema.u32,
+    duration: BorshSchema.u32,
+    supply: BorshSchema.u32,
+    windowStart: BorshSchema.u32,
+    issued: BorshSchema.u32,
+});
+
+export type Sku = {tier: number, prices: Price[], maxSupply: number, totalSupply: number};
+
+export const SKU_SCHEMA = BorshSchema.Struct({
+    tier: BorshSchema.u8,
+    prices: BorshSchema.Vec(Price.SCHEMA),
+    maxSupply: BorshSchema.u32,
+    totalSupply: BorshSchema.u32,
+});
+
 export class State {
-    version: StateVersion;
-    id: number;
-    owner: Uint8Array;
-    vaultBump: number;
-    totalSupply: number;
-    maxSupply: number;
-    beginTs: number;
-    endTs: number;
-    name: string;
-    signer: Uint8Array;
-    prices: Price[];
-    baseUrl: string;
-    withdrawCounter: number;
-
-    constructor(version: StateVersion, id: number, owner: Uint8Array, vaultBump: number, totalSupply: number, maxSupply: number, beginTs: number, endTs: number, name: string, signer: Uint8Array, prices: Price[], baseUrl: string, paymentAta: Uint8Array, withdrawCounter: number) {
-        this.version = version;
-        this.id = id;
-        this.owner = owner;
-        this.vaultBump = vaultBump;
-        this.totalSupply = totalSupply;
-        this.maxSupply = maxSupply;
-        this.beginTs = beginTs;
-        this.endTs = endTs;
-        this.name = name;
-        this.signer = signer;
-        this.prices = prices;
-        this.baseUrl = baseUrl;
-        this.withdrawCounter = withdrawCounter;
-    }
+    version!: StateVersion;
+    id!: number;
+    owner!: Uint8Array; // the owner of all lootboxes of the authority is kept in the access account
+    vaultBump!: number;
+    totalSupply!: number;
+    maxSupply!: number;
+    beginTs!: number;
+    endTs!: number;
+    name!: string;
+    signerSets!: SignerSet[];
+    prices!: Price[];
+    baseUrl!: string;
+    withdrawCounter!: number;
+    randomness!: RandomnessConfig;
+    serverSeedHash!: Uint8Array | null;
+    serverSeed!: Uint8Array | null;
+    legacyMessagesUntil!: number;
+    authority!: Uint8Array;
+    paused!: number;
+    referralBps!: number;
+    referralRegistration!: boolean;
+    maxTicketsPerWallet!: number;
+    windowLimit!: number;
+    windowDuration!: number;
+    allowlistRoot!: Uint8Array | null;
+    phases!: Phase[];
+    dropSchedule!: DropSchedule | null;
+    skus!: Sku[];
+    committedTickets!: number; // the randomness can't be changed while there are committed tickets
 }
 
 const schema = BorshSchema.Struct({
     version: BorshSchema.u8,
     id: BorshSchema.u16,
-    owner: BorshSchema.Array(BorshSchema.u8, 32),
+    owner: PUBKEY_SCHEMA,
     vaultBump: BorshSchema.u8,
     totalSupply: BorshSchema.u32,
     maxSupply: BorshSchema.u32,
     beginTs: BorshSchema.u32,
     endTs: BorshSchema.u32,
     name: BorshSchema.String,
-    signer: BorshSchema.Array(BorshSchema.u8, 33),
+    signerSets: BorshSchema.Vec(SIGNER_SET_SCHEMA),
     prices: BorshSchema.Vec(Price.SCHEMA),
     baseUrl: BorshSchema.String,
     withdrawCounter: BorshSchema.u32,
+    randomness: RANDOMNESS_SCHEMA,
+    serverSeedHash: BorshSchema.Option(PUBKEY_SCHEMA),
+    serverSeed: BorshSchema.Option(PUBKEY_SCHEMA),
+    legacyMessagesUntil: BorshSchema.u32,
+    authority: PUBKEY_SCHEMA,
+    paused: BorshSchema.u8,
+    referralBps: BorshSchema.u16,
+    referralRegistration: BorshSchema.bool,
+    maxTicketsPerWallet: BorshSchema.u32,
+    windowLimit: BorshSchema.u32,
+    windowDuration: BorshSchema.u32,
+    allowlistRoot: BorshSchema.Option(PUBKEY_SCHEMA),
+    phases: BorshSchema.Vec(PHASE_SCHEMA),
+    dropSchedule: BorshSchema.Option(DROP_SCHEDULE_SCHEMA),
+    skus: BorshSchema.Vec(SKU_SCHEMA),
+    committedTickets: BorshSchema.u32,
+});
+
+export enum Role {
+    Operator = 1,
+    Treasurer = 2,
+    Pauser = 4,
+}
+
+// ownership and roles of all lootboxes of the authority, they share the vault
+export type Access = {
+    version: number,
+    authority: Uint8Array,
+    bump: number,
+    owner: Uint8Array,
+    pendingOwner: Uint8Array | null,
+    roles: {account: Uint8Array, roles: number}[],
+};
+
+const accessSchema = BorshSchema.Struct({
+    version: BorshSchema.u8,
+    authority: PUBKEY_SCHEMA,
+    bump: BorshSchema.u8,
+    owner: PUBKEY_SCHEMA,
+    pendingOwner: BorshSchema.Option(PUBKEY_SCHEMA),
+    roles: BorshSchema.Vec(BorshSchema.Struct({
+        account: PUBKEY_SCHEMA,
+        roles: BorshSchema.u8,
+    })),
+});
+
+export type UserState = {
+    version: number,
+    state: Uint8Array,
+    owner: Uint8Array,
+    bump: number,
+    withdrawNonce: bigint, // each withdraw signature is bound to the current value
+    tickets: number,
+    recentTickets: number[],
+};
+
+const userSchema = BorshSchema.Struct({
+    version: BorshSchema.u8,
+    state: PUBKEY_SCHEMA,
+    owner: PUBKEY_SCHEMA,
+    bump: BorshSchema.u8,
+    withdrawNonce: BorshSchema.u64,
+    tickets: BorshSchema.u32,
+    recentTickets: BorshSchema.Array(BorshSchema.u32, 32),
+});
+
+// program-wide settings, see findConfigAddress
+export type Config = {
+    version: number,
+    bump: number,
+    authority: Uint8Array,
+    paused: boolean,
+    feeBps: number, // protocol fee from every payment
+    treasury: Uint8Array,
+    allowedMints: Uint8Array[],
+    maxTicketsPerBuy: number,
+};
+
+const configSchema = BorshSchema.Struct({
+    version: BorshSchema.u8,
+    bump: BorshSchema.u8,
+    authority: PUBKEY_SCHEMA,
+    paused: BorshSchema.bool,
+    feeBps: BorshSchema.u16,
+    treasury: PUBKEY_SCHEMA,
+    allowedMints: BorshSchema.Vec(PUBKEY_SCHEMA),
+    maxTicketsPerBuy: BorshSchema.u8,
 });
 
 export function createSeed(admin: PublicKey, lootboxId: number): Buffer[] {
@@ -87,10 +306,51 @@ export function findStateAddress(admin: PublicKey, lootboxId: number, programId:
     return PublicKey.findProgramAddressSync(seed, programId);
 }
 
-export function loadState(accountInfo: AccountInfo<Buffer | ParsedAccountData>): State {
+export function findConfigAddress(programId: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([Buffer.from(CONFIG_SEED)], programId);
+}
+
+// the access account may not exist, then the authority owns its lootboxes
+export function findAccessAddress(authority: PublicKey, programId: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([authority.toBuffer(), Buffer.from(ACCESS_SEED)], programId);
+}
+
+export function findUserAddress(statePda: PublicKey, owner: PublicKey, programId: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([statePda.toBuffer(), Buffer.from(USER_SEED), owner.toBuffer()], programId);
+}
+
+export function findReferrerAddress(statePda: PublicKey, referrer: PublicKey, programId: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([statePda.toBuffer(), Buffer.from(REFERRER_SEED), referrer.toBuffer()], programId);
+}
+
+export function findRewardsAddress(statePda: PublicKey, programId: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([statePda.toBuffer(), Buffer.from(REWARDS_SEED)], programId);
+}
+
+function accountData(accountInfo: AccountInfo<Buffer | ParsedAccountData>): Buffer {
     let data = accountInfo.data;
     if (!(data instanceof Buffer)) {
         throw new Error(`data is not a buffer, but ${typeof data}`);
     }
+    return data;
+}
+
+export function loadState(accountInfo: AccountInfo<Buffer | ParsedAccountData>): State {
+    let data = accountData(accountInfo);
+    if (data[0] != StateVersion.Version5) {
+        throw new Error(`State version ${data[0]} isn't supported, migrate it to ${StateVersion.Version5}`);
+    }
     return borshDeserialize<State>(schema, data);
 }
+
+export function loadAccess(accountInfo: AccountInfo<Buffer | ParsedAccountData>): Access {
+    return borshDeserialize<Access>(accessSchema, accountData(accountInfo));
+}
+
+export function loadUserState(accountInfo: AccountInfo<Buffer | ParsedAccountData>): UserState {
+    return borshDeserialize<UserState>(userSchema, accountData(accountInfo));
+}
+
+export function loadConfig(accountInfo: AccountInfo<Buffer | ParsedAccountData>): Config {
+    return borshDeserialize<Config>(configSchema, accountData(accountInfo));
+}
@@ -11,23 +11,37 @@ export class Ticket {
         lootboxId: BorshSchema.u16,
         issueIndex: BorshSchema.u32,
         externalId: BorshSchema.u32,
+        state: BorshSchema.Array(BorshSchema.u8, 32),
+        commitSlot: BorshSchema.u64,
+        tier: BorshSchema.u8,
+        randomnessAccount: BorshSchema.Array(BorshSchema.u8, 32),
     });
 
+    // Version2 layout, older tickets are shorter and have zeros in the missing fields
+    static readonly SIZE = 4 + 1 + 32 + 2 + 4 + 4 + 32 + 8 + 1 + 32;
+
     prefix: Array<number>;
     version: number;
     owner: Uint8Array;
     lootboxId: number;
     issueIndex: number;
     externalId: number;
+    state: Uint8Array; // zeros for tickets issued before Version1
+    commitSlot: bigint; // 0 until the owner commits to open the ticket
+    tier: number; // SKU tier
+    randomnessAccount: Uint8Array; // VRF request bound at the commit, zeros for SlotHashes
 
-
-    constructor(prefix: Array<number>, version: number, owner: Uint8Array, lootboxId: number, issueIndex: number, externalId: number) {
+    constructor(prefix: Array<number>, version: number, owner: Uint8Array, lootboxId: number, issueIndex: number, externalId: number, state: Uint8Array, commitSlot: bigint, tier: number, randomnessAccount: Uint8Array) {
         this.prefix = prefix;
         this.version = version;
         this.owner = owner;
         this.lootboxId = lootboxId;
         this.issueIndex = issueIndex;
         this.externalId = externalId;
+        this.state = state;
+        this.commitSlot = commitSlot;
+        this.tier = tier;
+        this.randomnessAccount = randomnessAccount;
     }
 
     public serialize(): Buffer {
@@ -39,6 +53,9 @@ export class Ticket {
         if (!(data instanceof Buffer)) {
             throw new Error(`data is not a buffer, but ${typeof data}`);
         }
+        if (data.length < Ticket.SIZE) {
+            data = Buffer.concat([data, Buffer.alloc(Ticket.SIZE - data.length)]);
+        }
         return borshDeserialize<Ticket>(Ticket.SCHEMA, data);
     }
 